@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use advent_of_code::bench::Stats;
+use advent_of_code::bench::millis;
+use advent_of_code::bench::timed;
+use advent_of_code::docking::Computer;
+use advent_of_code::docking::Program;
+
+const RUNS : usize = 20;
+
+// Masking as it was done before Mask held bitfields, each value goes through
+// a Vec<bool> and a string, kept here to compare against
+mod strings {
+    use std::collections::HashMap;
+
+    fn to_bits(num : u64, len : usize) -> Vec<bool> {
+        (0..len).map(
+            |i| (num >> (len - 1 - i)) % 2 == 1
+        ).collect()
+    }
+
+    fn mask_bit((ch, bit) : (char, &bool)) -> char {
+        match ch {
+            '0' => '0',
+            '1' => '1',
+            _ => if *bit { '1' } else { '0' }
+        }
+    }
+
+    fn one_mask_bit((ch, bit) : (char, &bool)) -> char {
+        match ch {
+            '1' => '1',
+            'X' => '0',
+            _ => if *bit { '1' } else { '0' }
+        }
+    }
+
+    fn make_floating(num : u64, index : &[usize]) -> Vec<u64> {
+        if index.is_empty() {
+            return vec!(num);
+        }
+        let mask : u64 = u64::pow(2, index[0] as u32);
+        let mut base = make_floating(num, &index[1..]);
+        base.extend(make_floating(num + mask, &index[1..]));
+        base
+    }
+
+    fn mask(mask : &str, num : u64) -> u64 {
+        let bits = to_bits(num, 36);
+        let masked : String = mask.chars().zip(bits.iter()).map(mask_bit).collect();
+        u64::from_str_radix(&masked, 2).unwrap()
+    }
+
+    fn decode(mask : &str, num : u64) -> Vec<u64> {
+        let bits = to_bits(num, 36);
+        let masked : String = mask.chars().zip(bits.iter()).map(one_mask_bit).collect();
+        let base = u64::from_str_radix(&masked, 2).unwrap();
+        let indices : Vec<usize> = mask.chars().enumerate().filter(
+            |(_, ch)| *ch == 'X'
+        ).map(
+            |(i, _)| 35 - i
+        ).collect();
+        make_floating(base, &indices)
+    }
+
+    // Sums of memory for both parts of the puzzle
+    pub fn run(lines : &[(Option<String>, u64, u64)]) -> (u128, u128) {
+        let mut values = HashMap::new();
+        let mut decoded = HashMap::new();
+        let mut current = String::new();
+        for (new_mask, address, value) in lines {
+            match new_mask {
+                Some(new_mask) => current = new_mask.clone(),
+                None => {
+                    values.insert(*address, mask(&current, *value));
+                    for floating in decode(&current, *address) {
+                        decoded.insert(floating, *value);
+                    }
+                }
+            }
+        }
+        (
+            values.values().map(|value| *value as u128).sum(),
+            decoded.values().map(|value| *value as u128).sum()
+        )
+    }
+}
+
+// Either a new mask or an assignment, for the string version
+fn parse(line : &str) -> (Option<String>, u64, u64) {
+    match line.strip_prefix("mask = ") {
+        Some(mask) => (Some(mask.to_string()), 0, 0),
+        None => {
+            let (address, value) = line.strip_prefix("mem[").unwrap().split_once("] = ").unwrap();
+            (None, address.parse().unwrap(), value.parse().unwrap())
+        }
+    }
+}
+
+fn measure<F>(f : F) -> ((u128, u128), Stats)
+  where F : Fn() -> (u128, u128)
+{
+    let mut samples : Vec<Duration> = Vec::new();
+    let mut result = (0, 0);
+    for _ in 0..RUNS {
+        let (sums, time) = timed(&f);
+        result = sums;
+        samples.push(time);
+    }
+    (result, Stats::from_samples(&samples))
+}
+
+fn main() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("day-14.txt");
+    let text = fs::read_to_string(&path).expect("could not read data/day-14.txt");
+    let program = Program::from_lines(text.lines().map(|line| line.to_string())).unwrap();
+    let lines : Vec<(Option<String>, u64, u64)> = text.lines().map(parse).collect();
+
+    let (bitfields, bitfield_stats) = measure(
+        || {
+            let mut cpu = Computer::new();
+            cpu.run(&program).unwrap();
+            let mut decoder = Computer::new();
+            decoder.run_decode(&program).unwrap();
+            (cpu.sum_variables(), decoder.sum_variables())
+        }
+    );
+    let (reference, string_stats) = measure(|| strings::run(&lines));
+    assert_eq!(bitfields, reference);
+
+    println!("day 14, both parts, {} runs", RUNS);
+    println!("{:>10} {:>10} {:>10} {:>10}", "", "mean ms", "min ms", "max ms");
+    for (name, stats) in [("strings", &string_stats), ("bitfields", &bitfield_stats)].iter() {
+        println!(
+            "{:>10} {:>10.3} {:>10.3} {:>10.3}",
+            name, millis(stats.mean), millis(stats.min), millis(stats.max)
+        );
+    }
+    println!("speedup {:.1}x", millis(string_stats.mean) / millis(bitfield_stats.mean));
+}
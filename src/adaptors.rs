@@ -1,22 +1,27 @@
 use std::collections::HashMap;
 
+use super::error::ParseError;
+use super::error::parse_lines;
+
 pub struct Adaptors {
     full_chain : Vec<usize>
 }
 
 impl Adaptors {
-    pub fn from_lines<I>(lines : I) -> Adaptors 
+    pub fn from_lines<I>(lines : I) -> Result<Adaptors, ParseError> 
       where I : Iterator<Item = String> 
     {
-        let mut full_chain : Vec<usize> = lines.filter_map(
-            |line| line.parse::<usize>().ok()
-        ).collect();
+        let mut full_chain : Vec<usize> = parse_lines(lines,
+            |line| line.parse::<usize>().map_err(
+                |_| ParseError::new(1, "expected an adaptor joltage")
+            )
+        )?;
         full_chain.push(0);
         full_chain.push(*full_chain.iter().max().unwrap()+3);
         full_chain.sort_unstable();
-        Adaptors {
+        Ok(Adaptors {
             full_chain : full_chain
-        }
+        })
     }
 
     pub fn joltage_differences(self : &Self) -> usize {
@@ -62,4 +67,29 @@ impl Adaptors {
         let mut cache : HashMap<usize, usize> = HashMap::new();
         self.number_arrangements_start_cached(0, &mut cache)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Adaptors;
+
+    fn adaptors(text : &str) -> Adaptors {
+        Adaptors::from_lines(text.split(',').map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn small_example() {
+        let data = adaptors("16,10,15,5,1,11,7,19,6,12,4");
+        assert_eq!(data.joltage_differences(), 7 * 5);
+        assert_eq!(data.number_arrangements(), 8);
+    }
+
+    #[test]
+    fn large_example() {
+        let data = adaptors(
+            "28,33,18,42,31,14,46,20,48,47,24,23,49,45,19,38,39,11,1,32,25,35,8,17,7,9,4,2,34,10,3"
+        );
+        assert_eq!(data.joltage_differences(), 22 * 10);
+        assert_eq!(data.number_arrangements(), 19208);
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::HashSet;
 use std::collections::HashMap;
 
+use super::error::ParseError;
+use super::error::parse_lines;
+
 struct Food {
     ingredients : HashSet<String>,
     allergens : HashSet<String>
@@ -11,15 +14,17 @@ pub struct Menu {
 }
 
 impl Food {
-    pub fn from_string(string : &str) -> Option<Food> {
-        string.split_once(" (contains ").and_then(
-            |(ingredients_str, rest)| rest.strip_suffix(")").map(
-                |allergens_str| Food {
-                    ingredients : ingredients_str.split(" ").map(|s| s.to_string()).collect(),
-                    allergens : allergens_str.split(", ").map(|s| s.to_string()).collect()
-                }
-            )
-        )
+    pub fn from_string(string : &str) -> Result<Food, ParseError> {
+        let (ingredients_str, rest) = string.split_once(" (contains ").ok_or(
+            ParseError::new(1, "expected an allergen list")
+        )?;
+        let allergens_str = rest.strip_suffix(")").ok_or(
+            ParseError::new(string.chars().count(), "expected closing ')'")
+        )?;
+        Ok(Food {
+            ingredients : ingredients_str.split(" ").map(|s| s.to_string()).collect(),
+            allergens : allergens_str.split(", ").map(|s| s.to_string()).collect()
+        })
     }
 
     pub fn count_ingredient(self : &Self, ingredient : &str) -> usize {
@@ -30,14 +35,12 @@ impl Food {
 }
 
 impl Menu {
-    pub fn from_lines<I>(lines : I) -> Menu
+    pub fn from_lines<I>(lines : I) -> Result<Menu, ParseError>
       where I : Iterator<Item = String>
     {
-        Menu {
-            items : lines.filter_map(
-                |line| Food::from_string(&line)
-            ).collect()
-        }
+        Ok(Menu {
+            items : parse_lines(lines, Food::from_string)?
+        })
     }
 
     fn allergens(self : &Self) -> HashSet<String> {
@@ -133,4 +136,28 @@ impl Menu {
             |allergen| ingredient_map[allergen].to_string()
         ).collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Menu;
+
+    const EXAMPLE : &str = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)\n\
+                            trh fvjkl sbzzf mxmxvkd (contains dairy)\n\
+                            sqjhc fvjkl (contains soy)\n\
+                            sqjhc mxmxvkd sbzzf (contains fish)";
+
+    fn menu() -> Menu {
+        Menu::from_lines(EXAMPLE.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn no_allergens() {
+        assert_eq!(menu().count_ingredients_no_allergens(), 5);
+    }
+
+    #[test]
+    fn ordered_by_allergen() {
+        assert_eq!(menu().ordered_ingredients_allergens(), vec!("mxmxvkd", "sqjhc", "fvjkl"));
+    }
+}
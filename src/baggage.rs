@@ -6,26 +6,31 @@ use petgraph::visit::Reversed;
 use petgraph::visit::EdgeRef;
 use petgraph::Outgoing;
 
-fn parse_bag(bag : &str) -> Option<(String, usize)> {
+use super::error::ParseError;
+
+#[allow(clippy::needless_return, clippy::single_char_add_str)]
+fn parse_bag(bag : &str) -> Result<(String, usize), ParseError> {
     let parts : Vec<&str> = bag.split(" ").collect();
     if parts.len() < 3 {
-        return None
-    }
-    if parts[0].parse::<usize>().is_err() {
-        return None
+        return Err(ParseError::new(1, "expected a count and a bag colour"))
     }
+    let num = parts[0].parse::<usize>().map_err(
+        |_| ParseError::new(1, "expected a bag count")
+    )?;
     let mut bag_name : String = parts[1].to_string();
     bag_name.push_str(" ");
     bag_name.push_str(parts[2]);
-    return Some( (bag_name, parts[0].parse::<usize>().unwrap()) )
+    return Ok( (bag_name, num) )
 }
 
-fn parse_contents(contents : &str) -> Vec<(String, usize)> {
+fn parse_contents(contents : &str) -> Result<Vec<(String, usize)>, ParseError> {
     if contents == "no other bags." {
-        return Vec::new()
+        return Ok(Vec::new())
     }
-    contents.split(", ").filter_map(
-        |bag| parse_bag(bag)
+    contents.split(", ").map(
+        |bag| parse_bag(bag).map_err(
+            |err| err.within(contents, bag)
+        )
     ).collect()
 }
 
@@ -42,14 +47,27 @@ impl Rules {
         }
     }
 
-    pub fn add_line(self : &mut Self, line : &str) {
-        let parsed = line.split_once(" bags contain ").map(
-            |(bag, contents)| (bag, parse_contents(contents))
-        );
-        match parsed {
-            Some((bag, contents)) => self.add_rule(bag, contents),
-            _ => ()
+    pub fn from_lines<I>(lines : I) -> Result<Rules, ParseError>
+      where I : Iterator<Item = String>
+    {
+        let mut rules = Rules::new();
+        for (index, line) in lines.enumerate() {
+            rules.add_line(&line).map_err(
+                |err| err.offset_lines(index)
+            )?;
         }
+        Ok(rules)
+    }
+
+    pub fn add_line(self : &mut Self, line : &str) -> Result<(), ParseError> {
+        let (bag, contents) = line.split_once(" bags contain ").ok_or(
+            ParseError::new(1, "expected '<colour> bags contain <contents>'")
+        )?;
+        let contents = parse_contents(contents).map_err(
+            |err| err.within(line, contents)
+        )?;
+        self.add_rule(bag, contents);
+        Ok(())
     }
 
     fn add_rule(self : &mut Self, bag : &str, contents : Vec<(String, usize)>) {
@@ -60,6 +78,7 @@ impl Rules {
         }
     }
 
+    #[allow(clippy::unnecessary_to_owned)]
     fn add_colour(self : &mut Self, bag : &str) -> NodeIndex<u32> {
         match self.colours.get(&bag.to_string()) {
             Some(bag_id) => *bag_id,
@@ -84,6 +103,7 @@ impl Rules {
         count
     }
 
+    #[allow(clippy::unnecessary_to_owned)]
     pub fn num_dependencies(self : &Self, node : &str) -> Option<usize> {
         self.colours.get(&node.to_string()).map(
             |start_id| self.num_dependencies_node(*start_id)
@@ -96,9 +116,51 @@ impl Rules {
         ).sum()
     }
 
+    #[allow(clippy::unnecessary_to_owned)]
     pub fn full_num_contained(self : &Self, node : &str) -> Option<usize> {
         self.colours.get(&node.to_string()).map(
             |start_id| self.full_num_contained_node(*start_id)
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rules;
+
+    fn rules(text : &str) -> Rules {
+        Rules::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let rules = rules(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.\n\
+             dark orange bags contain 3 bright white bags, 4 muted yellow bags.\n\
+             bright white bags contain 1 shiny gold bag.\n\
+             muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.\n\
+             shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.\n\
+             dark olive bags contain 3 faded blue bags, 4 dotted black bags.\n\
+             vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.\n\
+             faded blue bags contain no other bags.\n\
+             dotted black bags contain no other bags."
+        );
+        assert_eq!(rules.num_dependencies("shiny gold"), Some(4));
+        assert_eq!(rules.full_num_contained("shiny gold"), Some(32));
+        assert_eq!(rules.num_dependencies("mauve"), None);
+    }
+
+    #[test]
+    fn nested() {
+        let rules = rules(
+            "shiny gold bags contain 2 dark red bags.\n\
+             dark red bags contain 2 dark orange bags.\n\
+             dark orange bags contain 2 dark yellow bags.\n\
+             dark yellow bags contain 2 dark green bags.\n\
+             dark green bags contain 2 dark blue bags.\n\
+             dark blue bags contain 2 dark violet bags.\n\
+             dark violet bags contain no other bags."
+        );
+        assert_eq!(rules.full_num_contained("shiny gold"), Some(126));
+    }
+}
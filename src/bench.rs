@@ -0,0 +1,148 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use super::error::Error;
+use super::io::Source;
+use super::json as json;
+use super::solution::Entry;
+
+// Timings of one step over every repeated run
+#[derive(Clone, Debug)]
+pub struct Stats {
+    pub min : Duration,
+    pub mean : Duration,
+    pub max : Duration
+}
+
+pub struct DayTiming {
+    pub day : i8,
+    pub runs : usize,
+    pub parse : Stats,
+    pub parts : Vec<(u8, Stats)>
+}
+
+pub fn timed<T, F>(f : F) -> (T, Duration)
+  where F : FnOnce() -> T
+{
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+impl Stats {
+    pub fn from_samples(samples : &[Duration]) -> Stats {
+        let total : Duration = samples.iter().sum();
+        Stats {
+            min : samples.iter().min().cloned().unwrap_or_default(),
+            mean : total / (samples.len().max(1) as u32),
+            max : samples.iter().max().cloned().unwrap_or_default()
+        }
+    }
+
+    pub fn to_json(self : &Self) -> String {
+        json::object(&[
+            ("min_ms", format!("{:.3}", millis(self.min))),
+            ("mean_ms", format!("{:.3}", millis(self.mean))),
+            ("max_ms", format!("{:.3}", millis(self.max)))
+        ])
+    }
+}
+
+pub fn millis(duration : Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Parse and solve each part runs times, timing every step separately
+pub fn time_entry(entry : &Entry, source : &Source, runs : usize) -> Result<DayTiming, Error> {
+    let runs = runs.max(1);
+    let mut parse_samples = Vec::new();
+    let mut part_samples : Vec<(u8, Vec<Duration>)> = Vec::new();
+    for _ in 0..runs {
+        let (solution, parse_time) = timed(|| entry.parse(source));
+        let solution = solution?;
+        parse_samples.push(parse_time);
+        for part in 1..=2 {
+            let (answer, part_time) = timed(|| solution.solve(part));
+            if answer?.is_none() {
+                continue;
+            }
+            match part_samples.iter_mut().find(|(p, _)| *p == part) {
+                Some((_, samples)) => samples.push(part_time),
+                None => part_samples.push((part, vec!(part_time)))
+            }
+        }
+    }
+    Ok(DayTiming {
+        day : entry.day,
+        runs : runs,
+        parse : Stats::from_samples(&parse_samples),
+        parts : part_samples.iter().map(
+            |(part, samples)| (*part, Stats::from_samples(samples))
+        ).collect()
+    })
+}
+
+impl DayTiming {
+    pub fn part(self : &Self, part : u8) -> Option<&Stats> {
+        self.parts.iter().find(
+            |(p, _)| *p == part
+        ).map(
+            |(_, stats)| stats
+        )
+    }
+
+    pub fn total(self : &Self) -> Duration {
+        self.parse.mean + self.parts.iter().map(
+            |(_, stats)| stats.mean
+        ).sum::<Duration>()
+    }
+
+    pub fn to_json(self : &Self) -> String {
+        let mut fields = vec!(
+            ("day", self.day.to_string()),
+            ("runs", self.runs.to_string()),
+            ("parse", self.parse.to_json())
+        );
+        for (part, name) in [(1, "part1"), (2, "part2")].iter() {
+            if let Some(stats) = self.part(*part) {
+                fields.push((name, stats.to_json()));
+            }
+        }
+        json::object(&fields)
+    }
+}
+
+fn cell(stats : Option<&Stats>) -> String {
+    match stats {
+        Some(stats) => format!("{:>10.3} {:>10.3}", millis(stats.mean), millis(stats.min)),
+        None => format!("{:>10} {:>10}", "-", "-")
+    }
+}
+
+// Mean and minimum milliseconds for each step of each day
+pub fn table(timings : &[DayTiming]) -> String {
+    let mut lines = vec!(
+        format!(
+            "{:>3} {:>21} {:>21} {:>21} {:>10}",
+            "day", "parse ms (mean, min)", "part 1 ms (mean, min)", "part 2 ms (mean, min)", "total ms"
+        )
+    );
+    for timing in timings {
+        lines.push(format!(
+            "{:>3} {} {} {} {:>10.3}",
+            timing.day,
+            cell(Some(&timing.parse)),
+            cell(timing.part(1)),
+            cell(timing.part(2)),
+            millis(timing.total())
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn to_json(timings : &[DayTiming]) -> String {
+    let items : Vec<String> = timings.iter().map(
+        |timing| timing.to_json()
+    ).collect();
+    json::array(&items)
+}
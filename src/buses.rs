@@ -1,10 +1,15 @@
 use num_bigint::BigInt;
 use ring_algorithm::chinese_remainder_theorem;
 
+use super::error::ParseError;
+
 fn solve_remainder_problem(problem : &[(u64, usize)]) -> BigInt {
     let remainders : Vec<BigInt> = problem.iter().map(|(_,r)| BigInt::from(*r)).collect();
     let modulos : Vec<BigInt> = problem.iter().map(|(m,_)| BigInt::from(*m) ).collect();
-    -chinese_remainder_theorem::<BigInt>(&remainders, &modulos).unwrap()
+    let product : BigInt = modulos.iter().product();
+    // Bring the solution into 0..product to get the earliest timestamp
+    let solution = -chinese_remainder_theorem::<BigInt>(&remainders, &modulos).unwrap();
+    ((solution % &product) + &product) % &product
 }
 
 #[derive(Copy, Clone)]
@@ -13,14 +18,20 @@ pub struct Bus {
 }
 
 impl Bus {
-    pub fn from_string(string : &str) -> Bus {
-        Bus {
-            period : string.parse::<u64>().ok()
+    pub fn from_string(string : &str) -> Result<Bus, ParseError> {
+        if string == "x" {
+            return Ok(Bus { period : None });
         }
+        string.parse::<u64>().map(
+            |period| Bus { period : Some(period) }
+        ).map_err(
+            |_| ParseError::new(1, "expected a bus id or 'x'")
+        )
     }
     pub fn id(self : &Self) -> Option<u64> {
         self.period
     }
+    #[allow(clippy::manual_is_multiple_of)]
     pub fn departs(self : &Self, timestamp : u64) -> bool {
         self.period.map(
             |time| timestamp % time == 0
@@ -33,13 +44,19 @@ pub struct Timetable {
     buses : Vec<Bus>
 }
 impl Timetable {
-    pub fn from_lines(line1 : &str, line2 : &str) -> Timetable {
-        Timetable {
-            timestamp : line1.parse::<u64>().unwrap(),
-            buses : line2.split(",").map(
-                |string| Bus::from_string(string)
-            ).collect()
-        }
+    pub fn from_lines(line1 : &str, line2 : &str) -> Result<Timetable, ParseError> {
+        let timestamp = line1.parse::<u64>().map_err(
+            |_| ParseError::new(1, "expected a timestamp")
+        )?;
+        let buses = line2.split(",").map(
+            |string| Bus::from_string(string).map_err(
+                |err| err.within(line2, string).offset_lines(1)
+            )
+        ).collect::<Result<Vec<Bus>, ParseError>>()?;
+        Ok(Timetable {
+            timestamp : timestamp,
+            buses : buses
+        })
     }
     pub fn first_bus(self : &Self) -> Option<(u64, Bus)> {
         (0..).filter_map(
@@ -48,6 +65,7 @@ impl Timetable {
             )
         ).next()
     }
+    #[allow(clippy::filter_next, clippy::map_clone)]
     fn has_bus(self : &Self, timestamp : u64) -> Option<Bus> {
         self.buses.iter().filter(
             |bus| bus.departs(timestamp)
@@ -62,4 +80,44 @@ impl Timetable {
             ).collect();
         solve_remainder_problem(&remainder_problem)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+
+    use super::Timetable;
+
+    #[test]
+    fn first_bus() {
+        let timetable = Timetable::from_lines("939", "7,13,x,x,59,x,31,19").unwrap();
+        let (wait, bus) = timetable.first_bus().unwrap();
+        assert_eq!((wait, bus.id()), (5, Some(59)));
+    }
+
+    #[test]
+    fn first_congunction() {
+        let timetable = Timetable::from_lines("939", "7,13,x,x,59,x,31,19").unwrap();
+        assert_eq!(timetable.first_congunction(), BigInt::from(1068781));
+    }
+
+    #[test]
+    fn earliest_congunction() {
+        let examples = [
+            ("17,x,13,19", 3417),
+            ("67,7,59,61", 754018),
+            ("67,x,7,59,61", 779210),
+            ("1789,37,47,1889", 1202161486)
+        ];
+        for (buses, expected) in examples.iter() {
+            let timetable = Timetable::from_lines("0", buses).unwrap();
+            assert_eq!(timetable.first_congunction(), BigInt::from(*expected));
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let err = Timetable::from_lines("939", "7,y,13").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+}
@@ -0,0 +1,565 @@
+use num_bigint::BigInt;
+
+use super::error::Error;
+use super::io as io;
+use super::io::Source;
+use super::solution::Answer;
+use super::solution::Entry;
+use super::solution::Solution;
+use super::expenses as expenses;
+use super::passwords as passwords;
+use super::map as map;
+use super::passport as passport;
+use super::ticket as ticket;
+use super::customs as customs;
+use super::baggage as baggage;
+use super::cpu as cpu;
+use super::cipher as cipher;
+use super::adaptors as adaptors;
+use super::seating as seating;
+use super::directions as directions;
+use super::buses as buses;
+use super::docking as docking;
+use super::memory_game as memory_game;
+use super::ticket_scanning as ticket_scanning;
+use super::conway as conway;
+use super::expressions as expressions;
+use super::matching as matching;
+use super::tileset as tileset;
+use super::allergens as allergens;
+use super::crabs as crabs;
+use super::cups as cups;
+use super::floor as floor;
+use super::door as door;
+
+pub struct Day1 {
+    data : Vec<i64>
+}
+impl Solution for Day1 {
+    fn parse(source : &Source) -> Result<Day1, Error> {
+        Ok(Day1 { data : io::input_as_list(source, 1)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let (a, b) = expenses::find_2_summands(&self.data, 2020).unwrap();
+        Ok(Answer::new(a * b).with_detail("summands", format!("{},{}", a, b)))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let (a, b, c) = expenses::find_3_summands(&self.data, 2020).unwrap();
+        Ok(Some(Answer::new(a * b * c).with_detail("summands", format!("{},{},{}", a, b, c))))
+    }
+}
+
+pub struct Day2 {
+    data : passwords::Database
+}
+impl Solution for Day2 {
+    fn parse(source : &Source) -> Result<Day2, Error> {
+        Ok(Day2 { data : io::input_as_password_database(source, 2)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.count_valid(passwords::RuleSet::Sled)))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.count_valid(passwords::RuleSet::Toboggan))))
+    }
+}
+
+pub struct Day3 {
+    data : map::Map
+}
+impl Solution for Day3 {
+    fn parse(source : &Source) -> Result<Day3, Error> {
+        Ok(Day3 { data : io::input_as_map(source, 3)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.count_trees_path(1, 3)))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let a = self.data.count_trees_path(1, 1);
+        let b = self.data.count_trees_path(1, 3);
+        let c = self.data.count_trees_path(1, 5);
+        let d = self.data.count_trees_path(1, 7);
+        let e = self.data.count_trees_path(2, 1);
+        Ok(Some(
+            Answer::new(a*b*c*d*e)
+                .with_detail("right_1_down_1", a)
+                .with_detail("right_3_down_1", b)
+                .with_detail("right_5_down_1", c)
+                .with_detail("right_7_down_1", d)
+                .with_detail("right_1_down_2", e)
+        ))
+    }
+}
+
+pub struct Day4 {
+    data : Vec<passport::Passport>
+}
+impl Solution for Day4 {
+    fn parse(source : &Source) -> Result<Day4, Error> {
+        Ok(Day4 { data : io::input_as_passports(source, 4)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let num = self.data.iter().filter(
+            |passport| passport.required_fields_exist()
+        ).count();
+        Ok(Answer::new(num))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let num = self.data.iter().filter(
+            |passport| passport.required_fields_valid()
+        ).count();
+        Ok(Some(Answer::new(num)))
+    }
+}
+
+pub struct Day5 {
+    data : ticket::Plane
+}
+impl Solution for Day5 {
+    fn parse(source : &Source) -> Result<Day5, Error> {
+        Ok(Day5 { data : io::input_as_plane(source, 5)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.max()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.find_missing()[0])))
+    }
+}
+
+pub struct Day6 {
+    data : Vec<customs::Form>
+}
+impl Solution for Day6 {
+    fn parse(source : &Source) -> Result<Day6, Error> {
+        Ok(Day6 { data : io::input_as_forms(source, 6)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let num : usize = self.data.iter().map(
+            |form| form.num_any()
+        ).sum();
+        Ok(Answer::new(num))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let num : usize = self.data.iter().map(
+            |form| form.num_all()
+        ).sum();
+        Ok(Some(Answer::new(num)))
+    }
+}
+
+pub struct Day7 {
+    data : baggage::Rules
+}
+impl Solution for Day7 {
+    fn parse(source : &Source) -> Result<Day7, Error> {
+        Ok(Day7 { data : io::input_as_rules(source, 7)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.num_dependencies("shiny gold").unwrap()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.full_num_contained("shiny gold").unwrap())))
+    }
+}
+
+pub struct Day8 {
+    data : cpu::Program
+}
+impl Solution for Day8 {
+    fn parse(source : &Source) -> Result<Day8, Error> {
+        Ok(Day8 { data : io::input_as_program(source, 8)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let result = self.data.execute();
+        let answer = Answer::new(result.accumulator()).with_detail("steps", result.steps());
+        Ok(match result {
+            cpu::RunResult::Looped { from, to, .. } => answer.with_detail("loop", format!("{} -> {}", from, to)),
+            _ => answer
+        })
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let repair = self.data.fix().unwrap();
+        Ok(Some(
+            Answer::new(repair.accumulator)
+                .with_detail("index", repair.index)
+                .with_detail("instruction", &repair.instruction)
+        ))
+    }
+}
+
+pub struct Day9 {
+    data : cipher::CipherText
+}
+impl Solution for Day9 {
+    fn parse(source : &Source) -> Result<Day9, Error> {
+        Ok(Day9 { data : io::input_as_ciphertext(source, 9)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.first_prop(25).unwrap()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.weakness(25).unwrap())))
+    }
+}
+
+pub struct Day10 {
+    data : adaptors::Adaptors
+}
+impl Solution for Day10 {
+    fn parse(source : &Source) -> Result<Day10, Error> {
+        Ok(Day10 { data : io::input_as_adaptors(source, 10)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.joltage_differences()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.number_arrangements())))
+    }
+}
+
+pub struct Day11 {
+    data : seating::Seating
+}
+impl Solution for Day11 {
+    fn parse(source : &Source) -> Result<Day11, Error> {
+        Ok(Day11 { data : io::input_as_seating(source, 11)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut data = self.data.clone();
+        data.simulate(&seating::SeatingRules::Adjacent);
+        Ok(Answer::new(data.number_occupied()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut data = self.data.clone();
+        data.simulate(&seating::SeatingRules::Visible);
+        Ok(Some(Answer::new(data.number_occupied())))
+    }
+}
+
+pub struct Day12 {
+    data : Vec<directions::Action>
+}
+impl Solution for Day12 {
+    fn parse(source : &Source) -> Result<Day12, Error> {
+        Ok(Day12 { data : io::input_as_actions(source, 12)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut ship = directions::Ship::new();
+        ship.simulate(self.data.iter().cloned());
+        Ok(Answer::new(ship.distance()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut ship = directions::Ship::new();
+        ship.simulate_waypoint(self.data.iter().cloned());
+        Ok(Some(Answer::new(ship.distance())))
+    }
+}
+
+pub struct Day13 {
+    data : buses::Timetable
+}
+impl Solution for Day13 {
+    fn parse(source : &Source) -> Result<Day13, Error> {
+        Ok(Day13 { data : io::input_as_timetable(source, 13)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let (offset, bus) = self.data.first_bus().unwrap();
+        let id = bus.id().unwrap();
+        Ok(Answer::new(offset * id)
+            .with_detail("bus", id)
+            .with_detail("wait", offset))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(self.data.first_congunction())))
+    }
+}
+
+pub struct Day14 {
+    data : docking::Program
+}
+impl Solution for Day14 {
+    fn parse(source : &Source) -> Result<Day14, Error> {
+        Ok(Day14 { data : io::input_as_docking_program(source, 14)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut cpu = docking::Computer::new();
+        cpu.run(&self.data).unwrap();
+        Ok(Answer::new(cpu.sum_variables()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut cpu = docking::Computer::new();
+        cpu.run_decode(&self.data).unwrap();
+        Ok(Some(Answer::new(cpu.sum_variables())))
+    }
+}
+
+pub struct Day15 {
+    data : Vec<u64>
+}
+impl Solution for Day15 {
+    fn parse(source : &Source) -> Result<Day15, Error> {
+        Ok(Day15 { data : io::input_as_comma_list(source, 15)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut game = memory_game::Game::new(self.data.clone());
+        Ok(Answer::new(game.nth(2020-1).unwrap()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut game = memory_game::Game::new(self.data.clone());
+        Ok(Some(Answer::new(game.nth(30000000-1).unwrap())))
+    }
+}
+
+pub struct Day16 {
+    data : ticket_scanning::ScanningResult
+}
+impl Solution for Day16 {
+    fn parse(source : &Source) -> Result<Day16, Error> {
+        Ok(Day16 { data : io::input_as_scanning_results(source, 16)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.scanning_error_rate()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut data = self.data.clone();
+        data.discard_invalid();
+        let num : u64 = data.departures().iter().map(
+            |name| data.own_field(name).unwrap()
+        ).product();
+        Ok(Some(Answer::new(num)))
+    }
+}
+
+pub struct Day17 {
+    data3d : conway::Conway<conway::Position3D>,
+    data4d : conway::Conway<conway::Position4D>
+}
+impl Solution for Day17 {
+    fn parse(source : &Source) -> Result<Day17, Error> {
+        // Read once so both dimensions can be parsed from stdin
+        let data = source.read(17)?;
+        let text = Source::Text(&data);
+        Ok(Day17 {
+            data3d : io::input_as_conway(&text, 17)?,
+            data4d : io::input_as_conway(&text, 17)?
+        })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut data = self.data3d.clone();
+        data.simulate_n(6);
+        Ok(Answer::new(data.num_cubes()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut data = self.data4d.clone();
+        data.simulate_n(6);
+        Ok(Some(Answer::new(data.num_cubes())))
+    }
+}
+
+// Exact sums can not overflow, so the only failures left are per expression
+fn expression_total(expressions : &[expressions::Expression]) -> Result<BigInt, Error> {
+    expressions.iter().map(
+        |expression| expression.evaluate_big().map_err(
+            |err| expressions::ExpressionError::new(expression, err).into()
+        )
+    ).sum()
+}
+
+pub struct Day18 {
+    left_to_right : Vec<expressions::Expression>,
+    addition_first : Vec<expressions::Expression>
+}
+impl Solution for Day18 {
+    fn parse(source : &Source) -> Result<Day18, Error> {
+        let mut parsed = io::input_as_expressions(
+            source,
+            18,
+            &[expressions::Precedence::left_to_right(), expressions::Precedence::addition_first()]
+        )?;
+        let addition_first = parsed.pop().unwrap();
+        let left_to_right = parsed.pop().unwrap();
+        Ok(Day18 { left_to_right : left_to_right, addition_first : addition_first })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(expression_total(&self.left_to_right)?))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(Some(Answer::new(expression_total(&self.addition_first)?)))
+    }
+}
+
+pub struct Day19 {
+    rules : matching::RuleSet,
+    data : Vec<String>
+}
+impl Solution for Day19 {
+    fn parse(source : &Source) -> Result<Day19, Error> {
+        let (rules, data) = io::input_as_matching(source, 19)?;
+        Ok(Day19 { rules : rules, data : data })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let num : usize = self.data.iter().filter(
+            |line| self.rules.is_match(line)
+        ).count();
+        Ok(Answer::new(num))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut rules = self.rules.clone();
+        rules.add_new_rules();
+        let num : usize = self.data.iter().filter(
+            |line| rules.is_match(line)
+        ).count();
+        Ok(Some(Answer::new(num)))
+    }
+}
+
+pub struct Day20 {
+    tileset : tileset::TileSet
+}
+impl Solution for Day20 {
+    fn parse(source : &Source) -> Result<Day20, Error> {
+        Ok(Day20 { tileset : io::input_as_tileset(source, 20)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let solution = self.tileset.solve(12);
+        let corners = solution.corner_tiles();
+        let num : u64 = corners.iter().product();
+        let ids : Vec<String> = corners.iter().map(
+            |id| id.to_string()
+        ).collect();
+        Ok(Answer::new(num).with_detail("corners", ids.join(",")))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let solution = self.tileset.solve(12);
+        let picture = solution.picture();
+        let total = picture.total();
+        let monster_num = picture.search_monster().len();
+        Ok(Some(
+            Answer::new(total - monster_num)
+                .with_detail("hashes", total)
+                .with_detail("monster_cells", monster_num)
+        ))
+    }
+}
+
+pub struct Day21 {
+    menu : allergens::Menu
+}
+impl Solution for Day21 {
+    fn parse(source : &Source) -> Result<Day21, Error> {
+        Ok(Day21 { menu : io::input_as_menu(source, 21)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.menu.count_ingredients_no_allergens()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let ingredients = self.menu.ordered_ingredients_allergens();
+        Ok(Some(Answer::new(ingredients.join(","))))
+    }
+}
+
+pub struct Day22 {
+    data : crabs::Game
+}
+impl Solution for Day22 {
+    fn parse(source : &Source) -> Result<Day22, Error> {
+        Ok(Day22 { data : io::input_as_game(source, 22)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut data = self.data.clone();
+        data.play();
+        Ok(Answer::new(data.score()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut data = self.data.clone();
+        data.play_recursive();
+        Ok(Some(Answer::new(data.score())))
+    }
+}
+
+pub struct Day23 {
+    data : Vec<usize>
+}
+impl Solution for Day23 {
+    fn parse(source : &Source) -> Result<Day23, Error> {
+        Ok(Day23 { data : io::input_as_digits(source, 23)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut data = cups::Cups::from_cycle_ints(self.data.clone());
+        data.simulate(100);
+        Ok(Answer::new(data.labels(1)))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let vec : Vec<usize> = self.data.iter().cloned().chain(
+            (self.data.len()+1)..=1000000
+        ).collect();
+        let mut data = cups::Cups::from_cycle_ints(vec);
+        data.simulate(10000000);
+        let a = data.offset_labels(1, 1);
+        let b = data.offset_labels(1, 2);
+        Ok(Some(Answer::new(a * b).with_detail("cups", format!("{},{}", a, b))))
+    }
+}
+
+pub struct Day24 {
+    data : Vec<floor::Path>
+}
+impl Solution for Day24 {
+    fn parse(source : &Source) -> Result<Day24, Error> {
+        Ok(Day24 { data : io::input_as_paths(source, 24)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        let mut floor = floor::Floor::new();
+        floor.apply_paths(&self.data);
+        Ok(Answer::new(floor.len()))
+    }
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        let mut floor = floor::Floor::new();
+        floor.apply_paths(&self.data);
+        floor.simulate(100);
+        Ok(Some(Answer::new(floor.len())))
+    }
+}
+
+pub struct Day25 {
+    data : door::Handshake
+}
+impl Solution for Day25 {
+    fn parse(source : &Source) -> Result<Day25, Error> {
+        Ok(Day25 { data : io::input_as_handshake(source, 25)? })
+    }
+    fn part1(self : &Self) -> Result<Answer, Error> {
+        Ok(Answer::new(self.data.encryption_key()))
+    }
+}
+
+pub fn registry() -> Vec<Entry> {
+    vec!(
+        Entry::new::<Day1>(1),
+        Entry::new::<Day2>(2),
+        Entry::new::<Day3>(3),
+        Entry::new::<Day4>(4),
+        Entry::new::<Day5>(5),
+        Entry::new::<Day6>(6),
+        Entry::new::<Day7>(7),
+        Entry::new::<Day8>(8),
+        Entry::new::<Day9>(9),
+        Entry::new::<Day10>(10),
+        Entry::new::<Day11>(11),
+        Entry::new::<Day12>(12),
+        Entry::new::<Day13>(13),
+        Entry::new::<Day14>(14),
+        Entry::new::<Day15>(15),
+        Entry::new::<Day16>(16),
+        Entry::new::<Day17>(17),
+        Entry::new::<Day18>(18),
+        Entry::new::<Day19>(19),
+        Entry::new::<Day20>(20),
+        Entry::new::<Day21>(21),
+        Entry::new::<Day22>(22),
+        Entry::new::<Day23>(23),
+        Entry::new::<Day24>(24),
+        Entry::new::<Day25>(25)
+    )
+}
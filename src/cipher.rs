@@ -1,7 +1,11 @@
+use super::error::ParseError;
+use super::error::parse_lines;
+
 pub struct CipherText {
     data : Vec<usize>
 }
 
+#[allow(clippy::needless_return)]
 fn prop_holds(window : &[usize]) -> bool {
     let last = *window.last().unwrap();
     for i in 0..(window.len()-1) {
@@ -15,14 +19,16 @@ fn prop_holds(window : &[usize]) -> bool {
 }
 
 impl CipherText {
-    pub fn from_lines<I>(lines : I) -> CipherText 
+    pub fn from_lines<I>(lines : I) -> Result<CipherText, ParseError> 
       where I : Iterator<Item = String> 
     {
-        CipherText {
-            data : lines.filter_map(
-                |line| line.parse::<usize>().ok()
-            ).collect()
-        }
+        Ok(CipherText {
+            data : parse_lines(lines,
+                |line| line.parse::<usize>().map_err(
+                    |_| ParseError::new(1, "expected a number")
+                )
+            )?
+        })
     }
 
     pub fn first_prop(self : &Self, block : usize) -> Option<usize> {
@@ -33,6 +39,7 @@ impl CipherText {
         ).next()
     }
 
+    #[allow(clippy::needless_return)]
     pub fn weakness(self : &Self, block : usize) -> Option<usize> {
         let val = self.first_prop(block).unwrap();
         let len = self.data.len();
@@ -47,4 +54,25 @@ impl CipherText {
         }
         return None;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::CipherText;
+
+    const EXAMPLE : &str = "35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n\
+                            219\n299\n277\n309\n576";
+
+    fn ciphertext() -> CipherText {
+        CipherText::from_lines(EXAMPLE.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn first_prop() {
+        assert_eq!(ciphertext().first_prop(5), Some(127));
+    }
+
+    #[test]
+    fn weakness() {
+        assert_eq!(ciphertext().weakness(5), Some(62));
+    }
+}
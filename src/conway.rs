@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::hash::Hash;
 
+use super::error::ParseError;
+
 pub trait Position {
     fn surrounding(self : &Self) -> Vec<Self> 
       where Self : Sized;
@@ -75,6 +77,7 @@ impl Position for Position4D {
     }
 }
 
+#[derive(Clone)]
 pub struct Conway<P> {
     cubes : HashSet<P>
 }
@@ -82,18 +85,22 @@ pub struct Conway<P> {
 impl<P> Conway<P> 
   where P : Position + Eq + Hash + Copy
 {
-    pub fn from_lines<I>(lines : I) -> Conway<P>
+    pub fn from_lines<I>(lines : I) -> Result<Conway<P>, ParseError>
       where I : Iterator<Item = String> 
     {
         let mut cubes : HashSet<P> = HashSet::new();
         for (i, line) in lines.enumerate() {
             for (j, ch) in line.chars().enumerate() {
-                if ch == '#' {
-                    cubes.insert(P::from_2d(i as i32, j as i32));
+                match ch {
+                    '#' => { cubes.insert(P::from_2d(i as i32, j as i32)); },
+                    '.' => (),
+                    _ => return Err(
+                        ParseError::new(j + 1, "expected '#' or '.'").offset_lines(i)
+                    )
                 }
             }
         }
-        Conway { cubes : cubes }
+        Ok(Conway { cubes : cubes })
     }
 
     fn surrounding_occupied(self : &Self, cube : &P) -> usize {
@@ -139,4 +146,31 @@ impl<P> Conway<P>
             self.simulate_once();
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Conway;
+    use super::Position3D;
+    use super::Position4D;
+
+    const EXAMPLE : &str = ".#.\n..#\n###";
+
+    #[test]
+    fn three_dimensions() {
+        let mut data : Conway<Position3D> = Conway::from_lines(
+            EXAMPLE.lines().map(|line| line.to_string())
+        ).unwrap();
+        data.simulate_n(6);
+        assert_eq!(data.num_cubes(), 112);
+    }
+
+    #[test]
+    fn four_dimensions() {
+        let mut data : Conway<Position4D> = Conway::from_lines(
+            EXAMPLE.lines().map(|line| line.to_string())
+        ).unwrap();
+        data.simulate_n(6);
+        assert_eq!(data.num_cubes(), 848);
+    }
+}
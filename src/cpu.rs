@@ -1,132 +1,406 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-#[derive(Copy, Clone)]
-pub enum Instruction {
-    NOP,
-    ACC,
-    JMP
-}
+use super::error::ParseError;
+use super::error::parse_lines;
+
+mod assembler;
+mod debugger;
+mod graph;
+mod instruction;
+mod operations;
+mod profile;
+mod repair;
+pub mod repl;
+
+pub use self::debugger::Debugger;
+pub use self::debugger::Event;
+pub use self::debugger::TraceEntry;
+pub use self::graph::ControlFlow;
+pub use self::graph::Edge;
+pub use self::graph::Target;
+pub use self::instruction::Control;
+pub use self::instruction::Instruction;
+pub use self::instruction::InstructionSet;
+pub use self::instruction::Kind;
+pub use self::instruction::Machine;
+pub use self::instruction::Operand;
+pub use self::instruction::Operation;
+pub use self::instruction::Step;
+pub use self::instruction::REGISTERS;
+pub use self::operations::*;
+pub use self::profile::Profile;
+pub use self::repair::Patch;
+pub use self::repair::Repair;
+pub use self::repair::RepairSearch;
 
+// How many steps a run gets before it is given up on, registers can
+// keep changing forever without ever repeating a state
+pub const DEFAULT_MAX_STEPS : usize = 1_000_000;
+
+#[derive(Clone, PartialEq)]
 pub struct Program {
-    instructions : Vec<(Instruction, i64)>
+    instructions : Vec<Instruction>
+}
+
+// How a run ended, with the machine as it was at that point
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunResult {
+    // Ran past the last instruction or halted
+    Terminated { steps : usize, machine : Machine },
+    // The instruction at from went back to to, which would repeat a state
+    Looped { from : usize, to : usize, steps : usize, machine : Machine },
+    // The instruction at from jumped to target, outside of the program
+    OutOfBounds { from : usize, target : i64, steps : usize, machine : Machine },
+    // Still running at at when the step budget ran out
+    Exhausted { at : usize, steps : usize, machine : Machine }
 }
 
-pub struct ProgramState<'a> {
+struct ProgramState<'a> {
     counter : usize,
-    override_instruction : &'a HashMap<usize, Instruction>,
-    accumalator : i64,
-    instructions : HashSet<usize>,
+    override_instruction : HashMap<usize, Instruction>,
+    machine : Machine,
+    halted : bool,
+    // Where a jump out of the program would have gone
+    escaped : Option<i64>,
+    previous : usize,
+    steps : usize,
+    max_steps : usize,
+    profile : Option<Profile>,
+    // Registers are only part of the state once control can depend on them
+    branches : bool,
+    instructions : HashSet<(usize, Vec<i64>)>,
     program : &'a Program
 }
 
-impl Instruction {
-    fn parse_type(string : &str) -> Option<Instruction> {
-        match string {
-            "nop" => Some(Instruction::NOP),
-            "acc" => Some(Instruction::ACC),
-            "jmp" => Some(Instruction::JMP),
-            _ => None
-        }
-    }
-    fn flip(self : &Self) -> Instruction {
-        match self {
-            Instruction::NOP => Instruction::JMP,
-            Instruction::JMP => Instruction::NOP,
-            Instruction::ACC => Instruction::ACC
-        }
+impl Program {
+    pub fn new(instructions : Vec<Instruction>) -> Program {
+        Program { instructions : instructions }
     }
-}
-fn parse_line(line : &str) -> Option<(Instruction, i64)> {
-    let parsed = line.split_once(" ").map(
-        |(in_type, int)| (Instruction::parse_type(in_type), int.parse::<i64>().ok())
-    );
-    match parsed {
-        Some((Some(in_type), Some(int))) => Some( (in_type, int) ),
-        _ => None
+    pub fn from_lines<I>(lines : I) -> Result<Program, ParseError> 
+      where I : Iterator<Item = String> 
+    {
+        Program::from_lines_with(lines, &InstructionSet::default())
     }
-}
-impl Program {
-    pub fn from_lines<I>(lines : I) -> Program 
+    pub fn from_lines_with<I>(lines : I, set : &InstructionSet) -> Result<Program, ParseError> 
       where I : Iterator<Item = String> 
     {
-        Program {
-            instructions : lines.filter_map(
-                |line| parse_line(&line)
-            ).collect()
+        Ok(Program::new(parse_lines(lines, |line| set.parse(line))?))
+    }
+    // Whether any jump depends on the machine rather than only the program
+    pub fn has_branches(self : &Self) -> bool {
+        self.instructions.iter().any(
+            |instruction| matches!(instruction.control(), Control::Branch(_))
+        )
+    }
+    pub fn len(self : &Self) -> usize {
+        self.instructions.len()
+    }
+    pub fn is_empty(self : &Self) -> bool {
+        self.instructions.is_empty()
+    }
+    pub fn instruction(self : &Self, index : usize) -> Option<&Instruction> {
+        self.instructions.get(index)
+    }
+    pub fn instructions(self : &Self) -> &[Instruction] {
+        &self.instructions
+    }
+    pub fn push(self : &mut Self, instruction : Instruction) {
+        self.instructions.push(instruction);
+    }
+    // Swap in instruction at index, giving back the one it replaced
+    pub fn patch(self : &mut Self, index : usize, instruction : Instruction) -> Instruction {
+        std::mem::replace(&mut self.instructions[index], instruction)
+    }
+    pub fn patched(self : &Self, index : usize, instruction : Instruction) -> Program {
+        let mut program = self.clone();
+        program.patch(index, instruction);
+        program
+    }
+    fn run_override(self : &Self, overridden : HashMap<usize, Instruction>, max_steps : usize) -> RunResult {
+        let mut state = ProgramState::new(self, overridden).limited(max_steps);
+        state.run();
+        state.result()
+    }
+    pub fn execute(self : &Self) -> RunResult {
+        self.execute_with_limit(DEFAULT_MAX_STEPS)
+    }
+    pub fn execute_with_limit(self : &Self, max_steps : usize) -> RunResult {
+        self.run_override(HashMap::new(), max_steps)
+    }
+    pub fn run(self : &Self) -> i64 {
+        self.execute().accumulator()
+    }
+    // The machine as it was when the program looped or finished
+    pub fn run_machine(self : &Self) -> Machine {
+        self.execute().into_machine()
+    }
+}
+impl RunResult {
+    pub fn steps(self : &Self) -> usize {
+        match self {
+            RunResult::Terminated { steps, .. } => *steps,
+            RunResult::Looped { steps, .. } => *steps,
+            RunResult::OutOfBounds { steps, .. } => *steps,
+            RunResult::Exhausted { steps, .. } => *steps
         }
     }
-    fn run_override(self : &Self, overridden : &HashMap<usize, Instruction>) -> (bool, i64) {
-        let mut state = ProgramState::new(self, overridden);
-        let mut acc = 0;
-        while !state.looped() && !state.at_end() {
-            state.run_once();
-            acc = state.accumalator;
+    pub fn machine(self : &Self) -> &Machine {
+        match self {
+            RunResult::Terminated { machine, .. } => machine,
+            RunResult::Looped { machine, .. } => machine,
+            RunResult::OutOfBounds { machine, .. } => machine,
+            RunResult::Exhausted { machine, .. } => machine
         }
-        (state.at_end(), acc)
     }
-    pub fn run(self : &Self) -> i64 {
-        let dummy : HashMap<usize, Instruction> = HashMap::new();
-        let (_, acc) = self.run_override(&dummy);
-        acc
-    }
-    fn run_altered(self : &Self, flipped_index : usize) -> Option<i64> {
-        let mut flip : HashMap<usize, Instruction> = HashMap::new();
-        let (current, _) = self.instructions[flipped_index];
-        flip.insert(flipped_index, current.flip());
-        let (success, acc) = self.run_override(&flip);
-        if success {
-            Some(acc)
-        } else {
-            None
+    pub fn into_machine(self : Self) -> Machine {
+        match self {
+            RunResult::Terminated { machine, .. } => machine,
+            RunResult::Looped { machine, .. } => machine,
+            RunResult::OutOfBounds { machine, .. } => machine,
+            RunResult::Exhausted { machine, .. } => machine
         }
     }
-    pub fn fix(self : &Self) -> Option<i64> {
-        (0..self.instructions.len()).filter_map(
-            |index| self.run_altered(index)
-        ).next()
+    pub fn accumulator(self : &Self) -> i64 {
+        self.machine().accumulator()
+    }
+    pub fn terminated(self : &Self) -> bool {
+        matches!(self, RunResult::Terminated { .. })
     }
-    
 }
 impl<'a> ProgramState<'a> {
     fn new(
         program : &'a Program, 
-        override_instruction : &'a HashMap<usize, Instruction>
+        override_instruction : HashMap<usize, Instruction>
     ) -> ProgramState<'a> {
         ProgramState {
             program : program,
             counter : 0,
             override_instruction : override_instruction,
-            accumalator : 0,
+            machine : Machine::new(),
+            halted : false,
+            escaped : None,
+            previous : 0,
+            steps : 0,
+            max_steps : DEFAULT_MAX_STEPS,
+            profile : None,
+            branches : program.has_branches(),
             instructions : HashSet::new()
         }
     }
+    fn limited(mut self : Self, max_steps : usize) -> ProgramState<'a> {
+        self.max_steps = max_steps;
+        self
+    }
+    fn key(self : &Self) -> (usize, Vec<i64>) {
+        if self.branches {
+            (self.counter, self.machine.registers().to_vec())
+        } else {
+            (self.counter, Vec::new())
+        }
+    }
     fn looped(self : &Self) -> bool {
-        self.instructions.contains(&self.counter)
+        self.instructions.contains(&self.key())
     }
     fn at_end(self : &Self) -> bool {
-        self.counter == self.program.instructions.len()
+        self.halted || self.counter == self.program.instructions.len()
     }
-    fn run_once(self : &mut Self) {
-        self.instructions.insert(self.counter);
-        let (initial_instruction, delta) = self.program.instructions[self.counter];
-        let instruction = *self.override_instruction.get(
+    fn stopped(self : &Self) -> bool {
+        self.at_end() || self.escaped.is_some()
+    }
+    fn exhausted(self : &Self) -> bool {
+        self.steps >= self.max_steps
+    }
+    // Only meaningful once the state has stopped or looped
+    fn result(self : Self) -> RunResult {
+        let steps = self.steps;
+        if let Some(target) = self.escaped {
+            RunResult::OutOfBounds { from : self.counter, target : target, steps : steps, machine : self.machine }
+        } else if self.at_end() {
+            RunResult::Terminated { steps : steps, machine : self.machine }
+        } else if self.looped() {
+            RunResult::Looped { from : self.previous, to : self.counter, steps : steps, machine : self.machine }
+        } else {
+            RunResult::Exhausted { at : self.counter, steps : steps, machine : self.machine }
+        }
+    }
+    // The instruction about to run, taking overrides into account
+    fn current(self : &Self) -> &Instruction {
+        self.override_instruction.get(
             &self.counter
-        ).unwrap_or(&initial_instruction);
-        self.run_instruction((instruction, delta))
+        ).unwrap_or(&self.program.instructions[self.counter])
+    }
+    fn run(self : &mut Self) {
+        while !self.stopped() && !self.looped() && !self.exhausted() {
+            self.run_once();
+        }
     }
-    fn run_instruction(self : &mut Self, instruction : (Instruction, i64)) {
-        match instruction {
-            (Instruction::NOP, _) => {
+    fn run_once(self : &mut Self) {
+        self.instructions.insert(self.key());
+        self.previous = self.counter;
+        self.steps += 1;
+        let instruction = self.current().clone();
+        let before = self.machine.accumulator();
+        let step = instruction.execute(&mut self.machine);
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record(self.counter, self.machine.accumulator().wrapping_sub(before));
+        }
+        self.run_instruction(step)
+    }
+    fn run_instruction(self : &mut Self, step : Step) {
+        match step {
+            Step::Next => {
                 self.counter += 1;
             },
-            (Instruction::ACC, delta) => {
-                self.accumalator += delta;
-                self.counter += 1;
+            Step::Jump(delta) => {
+                let target = self.counter as i64 + delta;
+                if target < 0 || target > self.program.len() as i64 {
+                    self.escaped = Some(target);
+                } else {
+                    self.counter = target as usize;
+                }
             },
-            (Instruction::JMP, delta) => {
-                self.counter = ((self.counter as i64) + delta) as usize;
+            Step::Halt => {
+                self.halted = true;
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Programs shared by the tests of every cpu module
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::InstructionSet;
+    use super::Program;
+
+    // The day 8 example, which loops until the jmp at 7 becomes a nop
+    pub(crate) const EXAMPLE : &str = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+    pub(crate) fn program(text : &str) -> Program {
+        Program::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    pub(crate) fn example() -> Program {
+        program(EXAMPLE)
+    }
+
+    pub(crate) fn extended_program(text : &str) -> Program {
+        Program::from_lines_with(text.lines().map(|line| line.to_string()), &InstructionSet::extended()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Control;
+    use super::DEFAULT_MAX_STEPS;
+    use super::InstructionSet;
+    use super::Kind;
+    use super::Machine;
+    use super::Operand;
+    use super::Operation;
+    use super::Program;
+    use super::RunResult;
+    use super::Step;
+    use super::fixtures::example;
+    use super::fixtures::extended_program;
+
+    #[test]
+    fn run() {
+        assert_eq!(example().run(), 5);
+    }
+
+    #[test]
+    fn results() {
+        let looped = example().execute();
+        assert!(matches!(looped, RunResult::Looped { from : 4, to : 1, steps : 7, .. }));
+        assert_eq!(looped.accumulator(), 5);
+        let fixed = example().patched(7, example().instruction(7).unwrap().flipped().unwrap());
+        let terminated = fixed.execute();
+        assert!(terminated.terminated());
+        assert_eq!((terminated.steps(), terminated.accumulator()), (6, 8));
+        let lines = vec!("acc +1".to_string(), "jmp -2".to_string());
+        let escaped = Program::from_lines(lines.into_iter()).unwrap().execute();
+        assert!(matches!(escaped, RunResult::OutOfBounds { from : 1, target : -1, steps : 2, .. }));
+        assert_eq!(escaped.accumulator(), 1);
+        let lines = vec!("jmp +3".to_string(), "acc +1".to_string());
+        assert!(matches!(
+            Program::from_lines(lines.into_iter()).unwrap().execute(),
+            RunResult::OutOfBounds { from : 0, target : 3, .. }
+        ));
+        assert_eq!(extended_program("hlt\nacc +1").execute().steps(), 1);
+    }
+
+    #[test]
+    fn step_limit() {
+        // Counts up forever without repeating a state
+        let counting = extended_program("add a 1\njnz a -1");
+        let result = counting.execute_with_limit(1001);
+        assert!(matches!(result, RunResult::Exhausted { at : 1, steps : 1001, .. }));
+        assert_eq!(result.machine().registers()[1], 501);
+        assert!(matches!(counting.execute(), RunResult::Exhausted { steps : DEFAULT_MAX_STEPS, .. }));
+        // Looping is still reported when it happens within the budget
+        assert!(matches!(example().execute_with_limit(7), RunResult::Looped { steps : 7, .. }));
+        assert!(matches!(example().execute_with_limit(6), RunResult::Exhausted { at : 4, steps : 6, .. }));
+    }
+
+    #[test]
+    fn fix() {
+        assert_eq!(example().fix().map(|repair| repair.accumulator), Some(8));
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = vec!("nop +0".to_string(), "foo +1".to_string());
+        let err = Program::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+        let set = InstructionSet::extended();
+        let err = Program::from_lines_with(vec!("add +1".to_string()).into_iter(), &set).err().unwrap();
+        assert_eq!(err.message, "expected 2 argument(s) for add");
+        let err = Program::from_lines_with(vec!("add 3 +1".to_string()).into_iter(), &set).err().unwrap();
+        assert_eq!((err.column, err.message.as_str()), (5, "expected a register"));
+        let err = Program::from_lines_with(vec!("jnz a b".to_string()).into_iter(), &set).err().unwrap();
+        assert_eq!((err.column, err.message.as_str()), (7, "expected a signed number"));
+    }
+
+    #[test]
+    fn extended() {
+        let text = "set a 5\nset acc 1\nmul acc a\nsub a 1\njgz a -2\nout acc\nhlt\nacc +1000";
+        let program = extended_program(text);
+        let machine = program.run_machine();
+        assert_eq!(machine.output(), &[120]);
+        assert_eq!(machine.registers(), &[120, 0, 0, 0, 0]);
+        assert_eq!(program.run(), 120);
+        // Revisiting an instruction only loops once the registers repeat too
+        assert_eq!(extended_program("acc +2\nset a 1\nnop +0\njnz a -1").run(), 2);
+    }
+
+    #[test]
+    fn handheld_only() {
+        // Extended operations have to be asked for
+        let lines = vec!("set a 5".to_string());
+        assert!(Program::from_lines(lines.into_iter()).is_err());
+    }
+
+    struct Dbl;
+    static DBL : Dbl = Dbl;
+
+    impl Operation for Dbl {
+        fn name(self : &Self) -> &'static str { "dbl" }
+        fn operands(self : &Self) -> &'static [Kind] { &[Kind::Register] }
+        fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+            machine.set(args[0], machine.get(args[0]) * 2);
+            Step::Next
+        }
+        fn control(self : &Self, _ : &[Operand]) -> Control {
+            Control::Next
+        }
+    }
+
+    #[test]
+    fn custom_operation() {
+        let set = InstructionSet::handheld().with(&DBL);
+        let lines = vec!("acc +3".to_string(), "dbl acc".to_string(), "dbl acc".to_string());
+        let program = Program::from_lines_with(lines.into_iter(), &set).unwrap();
+        assert_eq!(program.run(), 12);
+    }
+}
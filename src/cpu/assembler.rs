@@ -0,0 +1,231 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ParseError;
+
+use super::Control;
+use super::Instruction;
+use super::InstructionSet;
+use super::Operand;
+use super::Program;
+use super::graph::Target;
+use super::graph::target;
+
+fn is_label(name : &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map(
+        |first| first.is_ascii_alphabetic() || first == '_'
+    ).unwrap_or(false) && chars.all(
+        |c| c.is_ascii_alphanumeric() || c == '_'
+    ) && Operand::register(name).is_none()
+}
+
+fn label(index : usize) -> String {
+    format!("l{}", index)
+}
+
+// Jumps take their offset from their last argument, which may name a label
+fn parse_instruction(
+    code : &str,
+    labels : &HashMap<&str, usize>,
+    index : usize,
+    set : &InstructionSet
+) -> Result<Instruction, ParseError> {
+    let (head, last) = match code.rsplit_once(' ') {
+        Some((head, last)) if is_label(last) => (head, last),
+        _ => return set.parse(code)
+    };
+    let destination = labels.get(last).ok_or(
+        ParseError::at(code, last, "unknown label")
+    )?;
+    let instruction = set.parse(&format!("{} +0", head))?;
+    if !matches!(instruction.control(), Control::Jump(_) | Control::Branch(_)) {
+        return Err(ParseError::at(code, last, "labels can only be used as jump offsets"));
+    }
+    let mut args = instruction.args().to_vec();
+    if let Some(offset) = args.last_mut() {
+        *offset = Operand::Immediate(*destination as i64 - index as i64);
+    }
+    Ok(Instruction::new(instruction.operation(), args))
+}
+
+impl Program {
+    pub fn assemble(text : &str) -> Result<Program, ParseError> {
+        Program::assemble_with(text, &InstructionSet::default())
+    }
+
+    // One instruction per line with "# ..." comments and "name:" labels, a
+    // label refers to the instruction after it
+    pub fn assemble_with(text : &str, set : &InstructionSet) -> Result<Program, ParseError> {
+        let mut labels : HashMap<&str, usize> = HashMap::new();
+        let mut lines : Vec<(usize, &str, &str)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let mut code = line.split('#').next().unwrap_or("").trim();
+            if let Some((name, rest)) = code.split_once(':') {
+                if !is_label(name) {
+                    return Err(ParseError::at(line, name, "expected a label name").offset_lines(number));
+                }
+                if labels.insert(name, lines.len()).is_some() {
+                    return Err(ParseError::at(line, name, "duplicate label").offset_lines(number));
+                }
+                code = rest.trim();
+            }
+            if !code.is_empty() {
+                lines.push((number, line, code));
+            }
+        }
+        let instructions = lines.iter().enumerate().map(
+            |(index, (number, line, code))| parse_instruction(code, &labels, index, set).map_err(
+                |err| err.within(line, code).offset_lines(*number)
+            )
+        ).collect::<Result<Vec<Instruction>, ParseError>>()?;
+        Ok(Program::new(instructions))
+    }
+
+    // Where each jump lands when that is inside the program or its end
+    fn destination(self : &Self, index : usize) -> Option<usize> {
+        let instruction = &self.instructions[index];
+        let control = instruction.control();
+        let offset = match control {
+            Control::Jump(offset) | Control::Branch(offset) => offset,
+            _ => return None
+        };
+        if instruction.args().last() != Some(&Operand::Immediate(offset)) {
+            return None;
+        }
+        match target(index, control, self.len()) {
+            Target::Index(destination) => Some(destination),
+            Target::End => Some(self.len()),
+            Target::Outside => None
+        }
+    }
+
+    // Source for assemble with every jump going to a label
+    pub fn disassemble(self : &Self) -> String {
+        let destinations : Vec<Option<usize>> = (0..self.len()).map(
+            |index| self.destination(index)
+        ).collect();
+        let labelled : BTreeSet<usize> = destinations.iter().flatten().cloned().collect();
+        let mut lines = Vec::new();
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if labelled.contains(&index) {
+                lines.push(format!("{}:", label(index)));
+            }
+            match destinations[index] {
+                Some(destination) => {
+                    let args = instruction.args();
+                    let mut parts = vec!(instruction.name().to_string());
+                    parts.extend(args[..args.len() - 1].iter().map(|arg| arg.to_string()));
+                    parts.push(label(destination));
+                    lines.push(parts.join(" "));
+                },
+                None => lines.push(instruction.to_string())
+            }
+        }
+        if labelled.contains(&self.len()) {
+            lines.push(format!("{}:", label(self.len())));
+        }
+        lines.join("\n")
+    }
+}
+
+// One instruction per line, exactly as from_lines reads them
+impl fmt::Display for Program {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Program {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.instructions.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cpu::InstructionSet;
+    use crate::cpu::Program;
+    use crate::cpu::fixtures::EXAMPLE;
+    use crate::cpu::fixtures::extended_program;
+    use crate::cpu::fixtures::program;
+
+    #[test]
+    fn round_trip() {
+        let example = program(EXAMPLE);
+        assert_eq!(example.to_string(), EXAMPLE);
+        assert_eq!(program(&example.to_string()), example);
+        assert_eq!(program("acc 22\nnop -0").to_string(), "acc +22\nnop +0");
+    }
+
+    #[test]
+    fn labels() {
+        let source = "# count a down from 3\n\
+                      set a 3\n\
+                      top: acc +1  # once per pass\n\
+                      sub a 1\n\
+                      jnz a top\n\
+                      jmp done\n\
+                      \n\
+                      acc +100\n\
+                      done:";
+        let counting = Program::assemble_with(source, &InstructionSet::extended()).unwrap();
+        assert_eq!(counting.to_string(), "set a +3\nacc +1\nsub a +1\njnz a -2\njmp +2\nacc +100");
+        assert_eq!(counting.run(), 3);
+    }
+
+    #[test]
+    fn disassemble() {
+        let example = program(EXAMPLE);
+        let source = example.disassemble();
+        assert_eq!(
+            source,
+            "nop +0\nl1:\nacc +1\njmp l6\nl3:\nacc +3\njmp l1\nacc -99\nl6:\nacc +1\njmp l3\nacc +6"
+        );
+        assert_eq!(Program::assemble(&source).unwrap(), example);
+        let ending = program("jmp +2\nacc +1\njmp -7");
+        assert_eq!(ending.disassemble(), "jmp l2\nacc +1\nl2:\njmp -7");
+        let to_end = extended_program("jnz acc +1");
+        assert_eq!(to_end.disassemble(), "jnz acc l1\nl1:");
+        assert_eq!(Program::assemble_with(&to_end.disassemble(), &InstructionSet::extended()).unwrap(), to_end);
+    }
+
+    #[test]
+    fn errors() {
+        let err = Program::assemble("jmp nowhere").err().unwrap();
+        assert_eq!((err.line, err.column, err.message.as_str()), (1, 5, "unknown label"));
+        let err = Program::assemble("x:\nacc x").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 5));
+        let err = Program::assemble("x:\nx: nop +0").err().unwrap();
+        assert_eq!((err.line, err.message.as_str()), (2, "duplicate label"));
+        let err = Program::assemble("  a: nop +0").err().unwrap();
+        assert_eq!((err.column, err.message.as_str()), (3, "expected a label name"));
+        let err = Program::assemble_with("start:\n  jnz start start", &InstructionSet::extended()).err().unwrap();
+        assert_eq!((err.line, err.column, err.message.as_str()), (2, 7, "expected a signed number"));
+        let err = Program::assemble("top:\njgz a top").err().unwrap();
+        assert_eq!((err.line, err.column, err.message.as_str()), (2, 1, "unknown instruction"));
+    }
+
+    #[test]
+    fn build_and_patch() {
+        let set = InstructionSet::handheld();
+        let mut program = Program::new(Vec::new());
+        program.push(set.parse("acc +5").unwrap());
+        program.push(set.parse("jmp -1").unwrap());
+        assert!(program.guaranteed_to_loop());
+        let replaced = program.patch(1, set.parse("nop -1").unwrap());
+        assert_eq!(replaced.to_string(), "jmp -1");
+        assert_eq!(program.run(), 5);
+        let patched = program.patched(0, set.parse("acc -2").unwrap());
+        assert_eq!(patched.to_string(), "acc -2\nnop -1");
+        assert_eq!(program.instructions().len(), 2);
+        assert_eq!(program.instruction(0).unwrap().to_string(), "acc +5");
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use super::Instruction;
+use super::Machine;
+use super::Program;
+use super::ProgramState;
+
+// Why the debugger stopped
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    // Ran the instruction at this index
+    Stepped(usize),
+    // The next instruction has a breakpoint on it
+    Breakpoint(usize),
+    // The accumulator changed while being watched
+    Watch { index : usize, from : i64, to : i64 },
+    // The instruction at this index would run a second time from the same state
+    Looped(usize),
+    // The instruction at index jumped outside of the program
+    OutOfBounds { index : usize, target : i64 },
+    // The step budget ran out before the instruction at this index
+    Exhausted(usize),
+    Terminated
+}
+
+#[derive(Clone)]
+pub struct TraceEntry {
+    pub index : usize,
+    pub instruction : Instruction,
+    // The accumulator after the instruction ran
+    pub accumulator : i64
+}
+
+pub struct Debugger<'a> {
+    state : ProgramState<'a>,
+    breakpoints : BTreeSet<usize>,
+    // The breakpoint run last stopped at, so it can resume past it
+    paused : Option<usize>,
+    watching : bool,
+    tracing : bool,
+    trace : Vec<TraceEntry>
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program : &'a Program) -> Debugger<'a> {
+        Debugger {
+            state : ProgramState::new(program, HashMap::new()),
+            breakpoints : BTreeSet::new(),
+            paused : None,
+            watching : false,
+            tracing : false,
+            trace : Vec::new()
+        }
+    }
+
+    pub fn counter(self : &Self) -> usize {
+        self.state.counter
+    }
+
+    pub fn machine(self : &Self) -> &Machine {
+        &self.state.machine
+    }
+
+    pub fn accumulator(self : &Self) -> i64 {
+        self.state.machine.accumulator()
+    }
+
+    // The instruction that will run next, if the program has not finished
+    pub fn current(self : &Self) -> Option<&Instruction> {
+        if self.finished() {
+            None
+        } else {
+            Some(self.state.current())
+        }
+    }
+
+    pub fn finished(self : &Self) -> bool {
+        self.state.stopped()
+    }
+
+    pub fn add_breakpoint(self : &mut Self, index : usize) {
+        self.breakpoints.insert(index);
+    }
+
+    pub fn remove_breakpoint(self : &mut Self, index : usize) -> bool {
+        self.breakpoints.remove(&index)
+    }
+
+    pub fn breakpoints(self : &Self) -> Vec<usize> {
+        self.breakpoints.iter().cloned().collect()
+    }
+
+    pub fn watch_accumulator(self : &mut Self, watching : bool) {
+        self.watching = watching;
+    }
+
+    pub fn watching(self : &Self) -> bool {
+        self.watching
+    }
+
+    pub fn set_tracing(self : &mut Self, tracing : bool) {
+        self.tracing = tracing;
+    }
+
+    // Stop with Exhausted after this many steps, trace and visited grow no further
+    pub fn set_max_steps(self : &mut Self, max_steps : usize) {
+        self.state.max_steps = max_steps;
+    }
+
+    pub fn trace(self : &Self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    // Indices of every instruction run so far, sorted without repeats
+    pub fn visited(self : &Self) -> Vec<usize> {
+        let visited : BTreeSet<usize> = self.state.instructions.iter().map(
+            |(index, _)| *index
+        ).collect();
+        visited.into_iter().collect()
+    }
+
+    // Run a single instruction unless the program has finished or looped
+    pub fn step(self : &mut Self) -> Event {
+        self.paused = None;
+        if self.state.at_end() {
+            return Event::Terminated;
+        }
+        let index = self.state.counter;
+        if let Some(target) = self.state.escaped {
+            return Event::OutOfBounds { index : index, target : target };
+        }
+        if self.state.looped() {
+            return Event::Looped(index);
+        }
+        if self.state.exhausted() {
+            return Event::Exhausted(index);
+        }
+        let from = self.accumulator();
+        let instruction = self.state.current().clone();
+        self.state.run_once();
+        let to = self.accumulator();
+        if self.tracing {
+            self.trace.push(TraceEntry {
+                index : index,
+                instruction : instruction,
+                accumulator : to
+            });
+        }
+        if let Some(target) = self.state.escaped {
+            return Event::OutOfBounds { index : index, target : target };
+        }
+        if self.watching && from != to {
+            Event::Watch { index : index, from : from, to : to }
+        } else {
+            Event::Stepped(index)
+        }
+    }
+
+    // Keep stepping until something other than a plain step happens
+    pub fn run(self : &mut Self) -> Event {
+        loop {
+            let counter = self.state.counter;
+            if self.breakpoints.contains(&counter) && self.paused != Some(counter) && !self.finished() {
+                self.paused = Some(counter);
+                return Event::Breakpoint(counter);
+            }
+            match self.step() {
+                Event::Stepped(_) => (),
+                event => return event
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Debugger;
+    use super::Event;
+    use crate::cpu::Program;
+    use crate::cpu::fixtures::example;
+    use crate::cpu::fixtures::extended_program;
+
+    #[test]
+    fn step() {
+        let program = example();
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.step(), Event::Stepped(0));
+        assert_eq!(debugger.step(), Event::Stepped(1));
+        assert_eq!((debugger.counter(), debugger.accumulator()), (2, 1));
+        assert_eq!(debugger.current().unwrap().to_string(), "jmp +4");
+    }
+
+    #[test]
+    fn breakpoints() {
+        let program = example();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(3);
+        assert_eq!(debugger.run(), Event::Breakpoint(3));
+        assert_eq!(debugger.accumulator(), 2);
+        assert!(debugger.remove_breakpoint(3));
+        assert_eq!(debugger.run(), Event::Looped(1));
+        assert_eq!(debugger.accumulator(), 5);
+        assert_eq!(debugger.visited(), vec!(0, 1, 2, 3, 4, 6, 7));
+    }
+
+    #[test]
+    fn first_instruction() {
+        let program = example();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(0);
+        assert_eq!(debugger.run(), Event::Breakpoint(0));
+        assert_eq!(debugger.accumulator(), 0);
+        assert_eq!(debugger.run(), Event::Looped(1));
+    }
+
+    #[test]
+    fn breakpoint_after_watch() {
+        let program = Program::from_lines(vec!("acc +1".to_string(), "acc +2".to_string(), "acc +3".to_string()).into_iter()).unwrap();
+        let mut debugger = Debugger::new(&program);
+        debugger.watch_accumulator(true);
+        debugger.add_breakpoint(1);
+        assert_eq!(debugger.run(), Event::Watch { index : 0, from : 0, to : 1 });
+        assert_eq!(debugger.run(), Event::Breakpoint(1));
+        assert_eq!(debugger.run(), Event::Watch { index : 1, from : 1, to : 3 });
+        assert_eq!(debugger.run(), Event::Watch { index : 2, from : 3, to : 6 });
+        assert_eq!(debugger.run(), Event::Terminated);
+    }
+
+    #[test]
+    fn watch() {
+        let program = example();
+        let mut debugger = Debugger::new(&program);
+        debugger.watch_accumulator(true);
+        assert_eq!(debugger.run(), Event::Watch { index : 1, from : 0, to : 1 });
+        assert_eq!(debugger.run(), Event::Watch { index : 6, from : 1, to : 2 });
+    }
+
+    #[test]
+    fn trace() {
+        let program = example();
+        let mut debugger = Debugger::new(&program);
+        debugger.set_tracing(true);
+        debugger.run();
+        let trace : Vec<(usize, String, i64)> = debugger.trace().iter().map(
+            |entry| (entry.index, entry.instruction.to_string(), entry.accumulator)
+        ).collect();
+        assert_eq!(trace[..3].to_vec(), vec!(
+            (0, "nop +0".to_string(), 0),
+            (1, "acc +1".to_string(), 1),
+            (2, "jmp +4".to_string(), 1)
+        ));
+        assert_eq!(trace.len(), 7);
+    }
+
+    #[test]
+    fn never_halts() {
+        let program = extended_program("add a 1\njnz a -1");
+        let mut debugger = Debugger::new(&program);
+        debugger.set_tracing(true);
+        debugger.set_max_steps(101);
+        assert_eq!(debugger.run(), Event::Exhausted(1));
+        assert_eq!(debugger.step(), Event::Exhausted(1));
+        assert_eq!(debugger.trace().len(), 101);
+        assert_eq!(debugger.machine().registers()[1], 51);
+        assert!(!debugger.finished());
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.run(), Event::Exhausted(0));
+        assert_eq!(debugger.visited(), vec!(0, 1));
+    }
+
+    #[test]
+    fn terminates() {
+        let program = Program::from_lines(vec!("acc +2".to_string()).into_iter()).unwrap();
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.run(), Event::Terminated);
+        assert_eq!(debugger.step(), Event::Terminated);
+        assert!(debugger.current().is_none());
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let program = Program::from_lines(vec!("acc +2".to_string(), "jmp +5".to_string()).into_iter()).unwrap();
+        let mut debugger = Debugger::new(&program);
+        debugger.set_tracing(true);
+        assert_eq!(debugger.run(), Event::OutOfBounds { index : 1, target : 6 });
+        let trace : Vec<usize> = debugger.trace().iter().map(|entry| entry.index).collect();
+        assert_eq!(trace, vec!(0, 1));
+        assert_eq!(debugger.step(), Event::OutOfBounds { index : 1, target : 6 });
+        assert!(debugger.finished());
+        assert!(debugger.current().is_none());
+        assert_eq!(debugger.accumulator(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::ops::Range;
+
+use petgraph::Graph;
+use petgraph::algo::tarjan_scc;
+use petgraph::dot::Dot;
+use petgraph::graph::NodeIndex;
+use petgraph::prelude::Dfs;
+use petgraph::visit::Reversed;
+
+use super::Control;
+use super::Program;
+
+// Where control goes after an instruction when it cannot depend on the machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    Index(usize),
+    End,
+    Outside
+}
+
+pub fn target(index : usize, control : Control, len : usize) -> Target {
+    let offset = match control {
+        Control::Next => 1,
+        Control::Jump(offset) | Control::Branch(offset) => offset,
+        Control::Halt => return Target::End
+    };
+    let next = index as i64 + offset;
+    if next < 0 || next > len as i64 {
+        Target::Outside
+    } else if next == len as i64 {
+        Target::End
+    } else {
+        Target::Index(next as usize)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Next,
+    Jump,
+    // The two ways out of a branch, which one is taken depends on the machine
+    Taken,
+    NotTaken,
+    Halt
+}
+
+impl fmt::Display for Edge {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Edge::Next => "next",
+            Edge::Jump => "jump",
+            Edge::Taken => "taken",
+            Edge::NotTaken => "not taken",
+            Edge::Halt => "halt"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// One node per instruction, in program order, followed by the end of the
+// program and everywhere outside of it
+pub struct ControlFlow<'a> {
+    program : &'a Program,
+    graph : Graph<Target, Edge>,
+    end : NodeIndex,
+    outside : NodeIndex
+}
+
+impl<'a> ControlFlow<'a> {
+    pub fn new(program : &'a Program) -> ControlFlow<'a> {
+        let len = program.len();
+        let mut graph = Graph::new();
+        for index in 0..len {
+            graph.add_node(Target::Index(index));
+        }
+        let end = graph.add_node(Target::End);
+        let outside = graph.add_node(Target::Outside);
+        let node = |target : Target| match target {
+            Target::Index(index) => NodeIndex::new(index),
+            Target::End => end,
+            Target::Outside => outside
+        };
+        for (index, instruction) in program.instructions.iter().enumerate() {
+            let from = NodeIndex::new(index);
+            let control = instruction.control();
+            let edges = match control {
+                Control::Next => vec!((Edge::Next, control)),
+                Control::Jump(_) => vec!((Edge::Jump, control)),
+                Control::Branch(_) => vec!((Edge::Taken, control), (Edge::NotTaken, Control::Next)),
+                Control::Halt => vec!((Edge::Halt, control))
+            };
+            for (edge, control) in edges {
+                graph.add_edge(from, node(target(index, control, len)), edge);
+            }
+        }
+        ControlFlow { program : program, graph : graph, end : end, outside : outside }
+    }
+
+    fn start(self : &Self) -> NodeIndex {
+        if self.program.is_empty() {
+            self.end
+        } else {
+            NodeIndex::new(0)
+        }
+    }
+
+    fn instructions<I>(self : &Self, nodes : I) -> Vec<bool>
+      where I : Iterator<Item = NodeIndex>
+    {
+        let mut found = vec!(false; self.program.len());
+        for node in nodes {
+            if let Target::Index(index) = self.graph[node] {
+                found[index] = true;
+            }
+        }
+        found
+    }
+
+    // Instructions on some path from the first one
+    pub fn reachable(self : &Self) -> Vec<bool> {
+        let mut dfs = Dfs::new(&self.graph, self.start());
+        self.instructions(std::iter::from_fn(|| dfs.next(&self.graph)))
+    }
+
+    pub fn dead(self : &Self) -> Vec<usize> {
+        indices(&self.reachable(), false)
+    }
+
+    // Instructions with some path to the end of the program
+    pub fn terminating(self : &Self) -> Vec<bool> {
+        let reversed = Reversed(&self.graph);
+        let mut dfs = Dfs::new(reversed, self.end);
+        self.instructions(std::iter::from_fn(|| dfs.next(reversed)))
+    }
+
+    pub fn leads_to_termination(self : &Self, index : usize) -> bool {
+        self.terminating().get(index).cloned().unwrap_or(false)
+    }
+
+    // Maximal runs of instructions only entered at the top and left at the bottom
+    pub fn basic_blocks(self : &Self) -> Vec<Range<usize>> {
+        let len = self.program.len();
+        let mut leaders = vec!(false; len + 1);
+        leaders[0] = true;
+        leaders[len] = true;
+        for (index, instruction) in self.program.instructions.iter().enumerate() {
+            let control = instruction.control();
+            if control != Control::Next {
+                leaders[index + 1] = true;
+                if let Target::Index(next) = target(index, control, len) {
+                    leaders[next] = true;
+                }
+            }
+        }
+        let starts = indices(&leaders, true);
+        starts.windows(2).map(
+            |pair| pair[0]..pair[1]
+        ).collect()
+    }
+
+    // Sets of instructions which can keep running into each other forever
+    pub fn loops(self : &Self) -> Vec<Vec<usize>> {
+        let mut loops : Vec<Vec<usize>> = tarjan_scc(&self.graph).iter().filter(
+            |component| component.len() > 1 || self.graph.contains_edge(component[0], component[0])
+        ).map(
+            |component| {
+                let mut indices : Vec<usize> = component.iter().map(|node| node.index()).collect();
+                indices.sort_unstable();
+                indices
+            }
+        ).collect();
+        loops.sort();
+        loops
+    }
+
+    // No path from the start ever leaves the program, so every run loops
+    pub fn guaranteed_to_loop(self : &Self) -> bool {
+        let mut dfs = Dfs::new(&self.graph, self.start());
+        while let Some(node) = dfs.next(&self.graph) {
+            if node == self.end || node == self.outside {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn to_dot(self : &Self) -> String {
+        let labelled = self.graph.map(
+            |_, target| match target {
+                Target::Index(index) => format!("{}: {}", index, self.program.instructions[*index]),
+                Target::End => "end".to_string(),
+                Target::Outside => "outside".to_string()
+            },
+            |_, edge| *edge
+        );
+        format!("{}", Dot::new(&labelled))
+    }
+}
+
+fn indices(flags : &[bool], value : bool) -> Vec<usize> {
+    flags.iter().enumerate().filter(
+        |(_, flag)| **flag == value
+    ).map(
+        |(index, _)| index
+    ).collect()
+}
+
+impl Program {
+    pub fn control_flow(self : &Self) -> ControlFlow<'_> {
+        ControlFlow::new(self)
+    }
+    pub fn guaranteed_to_loop(self : &Self) -> bool {
+        self.control_flow().guaranteed_to_loop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cpu::fixtures::example;
+    use crate::cpu::fixtures::extended_program;
+    use crate::cpu::fixtures::program;
+
+    #[test]
+    fn blocks() {
+        let looping = example();
+        let flow = looping.control_flow();
+        assert_eq!(flow.basic_blocks(), vec!(0..1, 1..3, 3..5, 5..6, 6..8, 8..9));
+        assert_eq!(flow.dead(), vec!(5, 8));
+        assert_eq!(flow.loops(), vec!(vec!(1, 2, 3, 4, 6, 7)));
+        assert!(flow.guaranteed_to_loop());
+    }
+
+    #[test]
+    fn termination() {
+        let looping = example();
+        let flow = looping.control_flow();
+        assert_eq!(super::indices(&flow.terminating(), true), vec!(8));
+        assert!(flow.leads_to_termination(8));
+        assert!(!flow.leads_to_termination(0));
+        let fixed = program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\nnop -4\nacc +6");
+        assert!(!fixed.guaranteed_to_loop());
+        assert!(fixed.control_flow().loops().is_empty());
+    }
+
+    #[test]
+    fn branches() {
+        let counting = extended_program("set a 3\nacc +1\nsub a 1\njnz a -2\nhlt");
+        let flow = counting.control_flow();
+        assert_eq!(flow.basic_blocks(), vec!(0..1, 1..4, 4..5));
+        assert_eq!(flow.loops(), vec!(vec!(1, 2, 3)));
+        assert!(!flow.guaranteed_to_loop());
+        assert!(program("jmp +0").guaranteed_to_loop());
+        assert!(!program("jmp +5").guaranteed_to_loop());
+        assert!(!program("").guaranteed_to_loop());
+    }
+
+    #[test]
+    fn dot() {
+        let dot = program("acc +1\njmp -1").control_flow().to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("label = \"1: jmp -1\""));
+        assert!(dot.contains("1 -> 0 [ label = \"jump\" ]"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ParseError;
+
+// General purpose registers, the accumulator is register 0
+pub const REGISTERS : [&str; 5] = ["acc", "a", "b", "c", "d"];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Immediate(i64),
+    Register(usize)
+}
+
+// What an operation accepts for each of its arguments
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Immediate,
+    Register,
+    Value
+}
+
+// What the interpreter should do after an instruction has run
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Next,
+    Jump(i64),
+    Halt
+}
+
+// Where control can go from an instruction, known without running it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Control {
+    Next,
+    Jump(i64),
+    // Either falls through or jumps depending on the machine
+    Branch(i64),
+    Halt
+}
+
+// Registers and output of a running program
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Machine {
+    registers : [i64; REGISTERS.len()],
+    output : Vec<i64>
+}
+
+// One opcode of the handheld, new families are added by implementing this
+pub trait Operation {
+    fn name(self : &Self) -> &'static str;
+
+    fn operands(self : &Self) -> &'static [Kind];
+
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step;
+
+    fn control(self : &Self, args : &[Operand]) -> Control;
+
+    // The operation swapped in when repairing a corrupted program
+    fn flipped(self : &Self) -> Option<&'static dyn Operation> {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct Instruction {
+    operation : &'static dyn Operation,
+    args : Vec<Operand>
+}
+
+// The operations a program may use, looked up by name
+#[derive(Clone)]
+pub struct InstructionSet {
+    operations : HashMap<&'static str, &'static dyn Operation>
+}
+
+impl Operand {
+    pub fn register(name : &str) -> Option<Operand> {
+        REGISTERS.iter().position(
+            |&register| register == name
+        ).map(Operand::Register)
+    }
+
+    fn from_string(string : &str, kind : Kind) -> Result<Operand, ParseError> {
+        match (Operand::register(string), kind) {
+            (Some(_), Kind::Immediate) => Err(ParseError::new(1, "expected a signed number")),
+            (Some(register), _) => Ok(register),
+            (None, Kind::Register) => Err(ParseError::new(1, "expected a register")),
+            (None, _) => string.parse::<i64>().map(Operand::Immediate).map_err(
+                |_| ParseError::new(1, "expected a signed number")
+            )
+        }
+    }
+}
+
+impl Machine {
+    pub fn new() -> Machine {
+        Machine::default()
+    }
+
+    pub fn get(self : &Self, operand : Operand) -> i64 {
+        match operand {
+            Operand::Immediate(value) => value,
+            Operand::Register(index) => self.registers[index]
+        }
+    }
+
+    // Writing to an immediate does nothing, parsing only allows registers
+    pub fn set(self : &mut Self, operand : Operand, value : i64) {
+        if let Operand::Register(index) = operand {
+            self.registers[index] = value;
+        }
+    }
+
+    pub fn accumulator(self : &Self) -> i64 {
+        self.registers[0]
+    }
+
+    pub fn registers(self : &Self) -> &[i64] {
+        &self.registers
+    }
+
+    pub fn output(self : &Self) -> &[i64] {
+        &self.output
+    }
+
+    pub fn write(self : &mut Self, value : i64) {
+        self.output.push(value);
+    }
+}
+
+impl Instruction {
+    pub fn new(operation : &'static dyn Operation, args : Vec<Operand>) -> Instruction {
+        Instruction { operation : operation, args : args }
+    }
+
+    pub fn operation(self : &Self) -> &'static dyn Operation {
+        self.operation
+    }
+
+    pub fn name(self : &Self) -> &'static str {
+        self.operation.name()
+    }
+
+    pub fn args(self : &Self) -> &[Operand] {
+        &self.args
+    }
+
+    pub fn execute(self : &Self, machine : &mut Machine) -> Step {
+        self.operation.execute(&self.args, machine)
+    }
+
+    pub fn control(self : &Self) -> Control {
+        self.operation.control(&self.args)
+    }
+
+    pub fn flipped(self : &Self) -> Option<Instruction> {
+        self.operation.flipped().map(
+            |operation| Instruction::new(operation, self.args.clone())
+        )
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Immediate(value) => write!(f, "{:+}", value),
+            Operand::Register(index) => write!(f, "{}", REGISTERS[*index])
+        }
+    }
+}
+
+// Written the way it is parsed, e.g. "acc +22"
+impl fmt::Display for Instruction {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+// Operations are equal when they share a name, as within an InstructionSet
+impl PartialEq for Instruction {
+    fn eq(self : &Self, other : &Self) -> bool {
+        self.name() == other.name() && self.args == other.args
+    }
+}
+
+impl fmt::Debug for Instruction {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl InstructionSet {
+    pub fn new() -> InstructionSet {
+        InstructionSet { operations : HashMap::new() }
+    }
+
+    pub fn with(mut self : Self, operation : &'static dyn Operation) -> InstructionSet {
+        self.operations.insert(operation.name(), operation);
+        self
+    }
+
+    pub fn get(self : &Self, name : &str) -> Option<&'static dyn Operation> {
+        self.operations.get(name).cloned()
+    }
+
+    // "<name> <arg> <arg>..." with arguments separated by single spaces
+    pub fn parse(self : &Self, line : &str) -> Result<Instruction, ParseError> {
+        let mut parts = line.split(' ');
+        let name = parts.next().unwrap_or("");
+        let operation = self.get(name).ok_or(
+            ParseError::new(1, "unknown instruction")
+        )?;
+        let parts : Vec<&str> = parts.collect();
+        let kinds = operation.operands();
+        if parts.len() != kinds.len() {
+            return Err(ParseError::new(
+                1,
+                &format!("expected {} argument(s) for {}", kinds.len(), name)
+            ));
+        }
+        let args = parts.iter().zip(kinds.iter()).map(
+            |(part, &kind)| Operand::from_string(part, kind).map_err(
+                |err| err.within(line, part)
+            )
+        ).collect::<Result<Vec<Operand>, ParseError>>()?;
+        Ok(Instruction::new(operation, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Instruction;
+    use super::InstructionSet;
+    use super::Kind;
+    use super::Machine;
+    use super::Operand;
+    use crate::cpu::JMP;
+    use crate::cpu::NOP;
+
+    #[test]
+    fn operands() {
+        assert_eq!(Operand::register("acc"), Some(Operand::Register(0)));
+        assert_eq!(Operand::register("d"), Some(Operand::Register(4)));
+        assert_eq!(Operand::register("e"), None);
+        assert_eq!(Operand::from_string("-7", Kind::Value), Ok(Operand::Immediate(-7)));
+        assert_eq!(Operand::from_string("b", Kind::Value), Ok(Operand::Register(2)));
+        assert!(Operand::from_string("b", Kind::Immediate).is_err());
+        assert!(Operand::from_string("7", Kind::Register).is_err());
+        assert_eq!(Operand::Immediate(3).to_string(), "+3");
+        assert_eq!(Operand::Register(1).to_string(), "a");
+    }
+
+    #[test]
+    fn machine() {
+        let mut machine = Machine::new();
+        machine.set(Operand::Register(0), 4);
+        machine.set(Operand::Immediate(9), 5);
+        assert_eq!(machine.get(Operand::Register(0)), 4);
+        assert_eq!(machine.get(Operand::Immediate(9)), 9);
+        assert_eq!(machine.accumulator(), 4);
+        machine.write(2);
+        assert_eq!(machine.output(), &[2]);
+    }
+
+    #[test]
+    fn instructions() {
+        let jmp = Instruction::new(&JMP, vec!(Operand::Immediate(-4)));
+        assert_eq!(jmp.to_string(), "jmp -4");
+        assert_eq!(jmp.flipped(), Some(Instruction::new(&NOP, vec!(Operand::Immediate(-4)))));
+        assert_ne!(jmp, Instruction::new(&JMP, vec!(Operand::Immediate(4))));
+        let set = InstructionSet::extended();
+        assert_eq!(set.parse("jmp -4"), Ok(jmp));
+        assert_eq!(set.parse("add b -2").unwrap().to_string(), "add b -2");
+        assert_eq!(set.parse("hlt").unwrap().args(), &[]);
+    }
+
+    #[test]
+    fn malformed() {
+        let set = InstructionSet::handheld();
+        assert_eq!(set.parse("foo +1").err().unwrap().message, "unknown instruction");
+        assert_eq!(set.parse("jmp").err().unwrap().message, "expected 1 argument(s) for jmp");
+        assert_eq!(set.parse("acc  +1").err().unwrap().message, "expected 1 argument(s) for acc");
+        let err = set.parse("acc x").err().unwrap();
+        assert_eq!((err.column, err.message.as_str()), (5, "expected a signed number"));
+    }
+}
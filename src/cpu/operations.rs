@@ -0,0 +1,302 @@
+use super::instruction::Control;
+use super::instruction::InstructionSet;
+use super::instruction::Kind;
+use super::instruction::Machine;
+use super::instruction::Operand;
+use super::instruction::Operation;
+use super::instruction::Step;
+
+// The handheld's original instructions
+pub struct Nop;
+pub struct Acc;
+pub struct Jmp;
+
+// Arithmetic on registers, the first argument is the destination
+pub struct Set;
+pub struct Add;
+pub struct Sub;
+pub struct Mul;
+
+// Jumps by the second argument when the first satisfies the condition
+pub struct JumpIf {
+    name : &'static str,
+    condition : fn(i64) -> bool
+}
+
+pub struct Hlt;
+pub struct Out;
+
+pub static NOP : Nop = Nop;
+pub static ACC : Acc = Acc;
+pub static JMP : Jmp = Jmp;
+pub static SET : Set = Set;
+pub static ADD : Add = Add;
+pub static SUB : Sub = Sub;
+pub static MUL : Mul = Mul;
+pub static JEZ : JumpIf = JumpIf { name : "jez", condition : |value| value == 0 };
+pub static JNZ : JumpIf = JumpIf { name : "jnz", condition : |value| value != 0 };
+pub static JGZ : JumpIf = JumpIf { name : "jgz", condition : |value| value > 0 };
+pub static JLZ : JumpIf = JumpIf { name : "jlz", condition : |value| value < 0 };
+pub static HLT : Hlt = Hlt;
+pub static OUT : Out = Out;
+
+const IMMEDIATE : [Kind; 1] = [Kind::Immediate];
+const VALUE : [Kind; 1] = [Kind::Value];
+const REGISTER_VALUE : [Kind; 2] = [Kind::Register, Kind::Value];
+const VALUE_IMMEDIATE : [Kind; 2] = [Kind::Value, Kind::Immediate];
+
+fn offset(args : &[Operand]) -> i64 {
+    match args.last() {
+        Some(Operand::Immediate(offset)) => *offset,
+        _ => 0
+    }
+}
+
+impl Operation for Nop {
+    fn name(self : &Self) -> &'static str { "nop" }
+    fn operands(self : &Self) -> &'static [Kind] { &IMMEDIATE }
+    fn execute(self : &Self, _ : &[Operand], _ : &mut Machine) -> Step {
+        Step::Next
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+    fn flipped(self : &Self) -> Option<&'static dyn Operation> {
+        Some(&JMP)
+    }
+}
+
+impl Operation for Acc {
+    fn name(self : &Self) -> &'static str { "acc" }
+    fn operands(self : &Self) -> &'static [Kind] { &IMMEDIATE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        let value = machine.accumulator().wrapping_add(machine.get(args[0]));
+        machine.set(Operand::Register(0), value);
+        Step::Next
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl Operation for Jmp {
+    fn name(self : &Self) -> &'static str { "jmp" }
+    fn operands(self : &Self) -> &'static [Kind] { &IMMEDIATE }
+    fn execute(self : &Self, args : &[Operand], _ : &mut Machine) -> Step {
+        Step::Jump(offset(args))
+    }
+    fn control(self : &Self, args : &[Operand]) -> Control {
+        Control::Jump(offset(args))
+    }
+    fn flipped(self : &Self) -> Option<&'static dyn Operation> {
+        Some(&NOP)
+    }
+}
+
+fn arithmetic(args : &[Operand], machine : &mut Machine, op : fn(i64, i64) -> i64) -> Step {
+    let value = op(machine.get(args[0]), machine.get(args[1]));
+    machine.set(args[0], value);
+    Step::Next
+}
+
+impl Operation for Set {
+    fn name(self : &Self) -> &'static str { "set" }
+    fn operands(self : &Self) -> &'static [Kind] { &REGISTER_VALUE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        arithmetic(args, machine, |_, value| value)
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl Operation for Add {
+    fn name(self : &Self) -> &'static str { "add" }
+    fn operands(self : &Self) -> &'static [Kind] { &REGISTER_VALUE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        arithmetic(args, machine, i64::wrapping_add)
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl Operation for Sub {
+    fn name(self : &Self) -> &'static str { "sub" }
+    fn operands(self : &Self) -> &'static [Kind] { &REGISTER_VALUE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        arithmetic(args, machine, i64::wrapping_sub)
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl Operation for Mul {
+    fn name(self : &Self) -> &'static str { "mul" }
+    fn operands(self : &Self) -> &'static [Kind] { &REGISTER_VALUE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        arithmetic(args, machine, i64::wrapping_mul)
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl Operation for JumpIf {
+    fn name(self : &Self) -> &'static str { self.name }
+    fn operands(self : &Self) -> &'static [Kind] { &VALUE_IMMEDIATE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        if (self.condition)(machine.get(args[0])) {
+            Step::Jump(offset(args))
+        } else {
+            Step::Next
+        }
+    }
+    fn control(self : &Self, args : &[Operand]) -> Control {
+        Control::Branch(offset(args))
+    }
+}
+
+impl Operation for Hlt {
+    fn name(self : &Self) -> &'static str { "hlt" }
+    fn operands(self : &Self) -> &'static [Kind] { &[] }
+    fn execute(self : &Self, _ : &[Operand], _ : &mut Machine) -> Step {
+        Step::Halt
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Halt
+    }
+}
+
+impl Operation for Out {
+    fn name(self : &Self) -> &'static str { "out" }
+    fn operands(self : &Self) -> &'static [Kind] { &VALUE }
+    fn execute(self : &Self, args : &[Operand], machine : &mut Machine) -> Step {
+        machine.write(machine.get(args[0]));
+        Step::Next
+    }
+    fn control(self : &Self, _ : &[Operand]) -> Control {
+        Control::Next
+    }
+}
+
+impl InstructionSet {
+    // Only nop, acc and jmp as in the original puzzle
+    pub fn handheld() -> InstructionSet {
+        InstructionSet::new()
+            .with(&NOP)
+            .with(&ACC)
+            .with(&JMP)
+    }
+
+    pub fn extended() -> InstructionSet {
+        InstructionSet::handheld()
+            .with(&SET)
+            .with(&ADD)
+            .with(&SUB)
+            .with(&MUL)
+            .with(&JEZ)
+            .with(&JNZ)
+            .with(&JGZ)
+            .with(&JLZ)
+            .with(&HLT)
+            .with(&OUT)
+    }
+}
+
+impl Default for InstructionSet {
+    fn default() -> InstructionSet {
+        InstructionSet::handheld()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Control;
+    use super::InstructionSet;
+    use super::Machine;
+    use super::Operand;
+    use super::Operation;
+    use super::Step;
+    use super::ACC;
+    use super::ADD;
+    use super::JEZ;
+    use super::JGZ;
+    use super::JLZ;
+    use super::JMP;
+    use super::JNZ;
+    use super::MUL;
+    use super::NOP;
+    use super::OUT;
+    use super::SET;
+    use super::SUB;
+
+    const A : Operand = Operand::Register(1);
+
+    fn with_a(a : i64) -> Machine {
+        let mut machine = Machine::new();
+        machine.set(A, a);
+        machine
+    }
+
+    #[test]
+    fn handheld() {
+        let mut machine = Machine::new();
+        assert_eq!(NOP.execute(&[Operand::Immediate(5)], &mut machine), Step::Next);
+        assert_eq!(ACC.execute(&[Operand::Immediate(-3)], &mut machine), Step::Next);
+        assert_eq!(machine.accumulator(), -3);
+        assert_eq!(JMP.execute(&[Operand::Immediate(-2)], &mut machine), Step::Jump(-2));
+        assert_eq!(JMP.control(&[Operand::Immediate(4)]), Control::Jump(4));
+        assert_eq!(NOP.control(&[Operand::Immediate(4)]), Control::Next);
+        assert_eq!(NOP.flipped().map(|op| op.name()), Some("jmp"));
+        assert_eq!(JMP.flipped().map(|op| op.name()), Some("nop"));
+        assert!(ACC.flipped().is_none());
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut machine = Machine::new();
+        ACC.execute(&[Operand::Immediate(i64::MAX)], &mut machine);
+        ACC.execute(&[Operand::Immediate(1)], &mut machine);
+        assert_eq!(machine.accumulator(), i64::MIN);
+        let mut machine = with_a(i64::MIN);
+        SUB.execute(&[A, Operand::Immediate(1)], &mut machine);
+        assert_eq!(machine.get(A), i64::MAX);
+        ADD.execute(&[A, Operand::Immediate(1)], &mut machine);
+        MUL.execute(&[A, Operand::Immediate(2)], &mut machine);
+        assert_eq!(machine.get(A), 0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut machine = with_a(6);
+        SET.execute(&[Operand::Register(2), A], &mut machine);
+        ADD.execute(&[A, Operand::Immediate(4)], &mut machine);
+        SUB.execute(&[A, Operand::Register(2)], &mut machine);
+        MUL.execute(&[A, Operand::Immediate(-3)], &mut machine);
+        OUT.execute(&[A], &mut machine);
+        assert_eq!(machine.registers(), &[0, -12, 6, 0, 0]);
+        assert_eq!(machine.output(), &[-12]);
+    }
+
+    #[test]
+    fn conditions() {
+        let taken = |op : &dyn Operation, a : i64| {
+            op.execute(&[A, Operand::Immediate(3)], &mut with_a(a)) == Step::Jump(3)
+        };
+        assert_eq!((taken(&JEZ, 0), taken(&JEZ, 1)), (true, false));
+        assert_eq!((taken(&JNZ, 0), taken(&JNZ, -1)), (false, true));
+        assert_eq!((taken(&JGZ, 0), taken(&JGZ, 1)), (false, true));
+        assert_eq!((taken(&JLZ, 0), taken(&JLZ, -1)), (false, true));
+        assert_eq!(JNZ.control(&[A, Operand::Immediate(-2)]), Control::Branch(-2));
+    }
+
+    #[test]
+    fn sets() {
+        assert!(InstructionSet::handheld().get("acc").is_some());
+        assert!(InstructionSet::handheld().get("set").is_none());
+        assert!(InstructionSet::default().get("hlt").is_none());
+        assert!(InstructionSet::extended().get("hlt").is_some());
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use super::Instruction;
+use super::Program;
+use super::ProgramState;
+use super::RunResult;
+
+// How often each instruction ran and how much it moved the accumulator,
+// against the program as it ran with any overrides in place
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    instructions : Vec<Instruction>,
+    overridden : Vec<bool>,
+    hits : Vec<usize>,
+    deltas : Vec<i64>
+}
+
+impl Profile {
+    fn new(program : &Program, overridden : &HashMap<usize, Instruction>) -> Profile {
+        let len = program.len();
+        Profile {
+            instructions : (0..len).map(
+                |index| overridden.get(&index).unwrap_or(&program.instructions[index]).clone()
+            ).collect(),
+            overridden : (0..len).map(|index| overridden.contains_key(&index)).collect(),
+            hits : vec!(0; len),
+            deltas : vec!(0; len)
+        }
+    }
+
+    pub fn record(self : &mut Self, index : usize, delta : i64) {
+        self.hits[index] += 1;
+        self.deltas[index] = self.deltas[index].wrapping_add(delta);
+    }
+
+    pub fn hits(self : &Self) -> &[usize] {
+        &self.hits
+    }
+
+    // Total change to the accumulator made by each instruction
+    pub fn deltas(self : &Self) -> &[i64] {
+        &self.deltas
+    }
+
+    pub fn steps(self : &Self) -> usize {
+        self.hits.iter().sum()
+    }
+
+    // The count most run instructions, ties in program order
+    pub fn hottest(self : &Self, count : usize) -> Vec<(usize, usize)> {
+        let mut hot : Vec<(usize, usize)> = self.hits.iter().cloned().enumerate().filter(
+            |(_, hits)| *hits > 0
+        ).collect();
+        hot.sort_by_key(|(index, hits)| (std::cmp::Reverse(*hits), *index));
+        hot.truncate(count);
+        hot
+    }
+
+    // The program with hits and accumulator change beside each instruction,
+    // overridden instructions are marked with *
+    pub fn listing(self : &Self) -> String {
+        let mut lines = vec!(format!("{:>8} {:>8} {:>5}  {}", "hits", "acc", "index", "instruction"));
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            let (hits, delta) = match self.hits[index] {
+                0 => ("-".to_string(), "-".to_string()),
+                hits => (hits.to_string(), format!("{:+}", self.deltas[index]))
+            };
+            let marker = if self.overridden[index] { "*" } else { " " };
+            lines.push(format!("{:>8} {:>8} {:>5} {}{}", hits, delta, index, marker, instruction));
+        }
+        lines.join("\n")
+    }
+}
+
+impl<'a> ProgramState<'a> {
+    fn profiled(mut self : Self) -> ProgramState<'a> {
+        self.profile = Some(Profile::new(self.program, &self.override_instruction));
+        self
+    }
+}
+
+impl Program {
+    pub fn profile(self : &Self) -> (RunResult, Profile) {
+        self.profile_override(HashMap::new())
+    }
+
+    pub fn profile_override(self : &Self, overridden : HashMap<usize, Instruction>) -> (RunResult, Profile) {
+        let mut state = ProgramState::new(self, overridden).profiled();
+        state.run();
+        let profile = state.profile.take().unwrap();
+        (state.result(), profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::cpu::InstructionSet;
+    use crate::cpu::fixtures::example;
+    use crate::cpu::fixtures::extended_program;
+
+    #[test]
+    fn counts() {
+        let counting = extended_program("set a 3\nacc +2\nsub a 1\njnz a -2\nacc -1");
+        let (result, profile) = counting.profile();
+        assert!(result.terminated());
+        assert_eq!(profile.hits(), &[1, 3, 3, 3, 1]);
+        assert_eq!(profile.deltas(), &[0, 6, 0, 0, -1]);
+        assert_eq!(profile.steps(), result.steps());
+        assert_eq!(profile.hottest(2), vec!((1, 3), (2, 3)));
+    }
+
+    #[test]
+    fn overrides() {
+        let example = example();
+        let (looped, profile) = example.profile();
+        assert!(!looped.terminated());
+        assert_eq!(profile.hits(), &[1, 1, 1, 1, 1, 0, 1, 1, 0]);
+        let mut overridden = HashMap::new();
+        overridden.insert(7, InstructionSet::handheld().parse("nop -4").unwrap());
+        let (fixed, profile) = example.profile_override(overridden);
+        assert_eq!(fixed.accumulator(), 8);
+        assert_eq!(profile.hits(), &[1, 1, 1, 0, 0, 0, 1, 1, 1]);
+        let listing = profile.listing();
+        let listing : Vec<&str> = listing.lines().collect();
+        assert_eq!(listing[0], "    hits      acc index  instruction");
+        assert_eq!(listing[2], "       1       +1     1  acc +1");
+        assert_eq!(listing[4], "       -        -     3  acc +3");
+        assert_eq!(listing[8], "       1       +0     7 *nop -4");
+    }
+}
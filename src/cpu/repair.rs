@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::Instruction;
+use super::NOP;
+use super::Operand;
+use super::Operation;
+use super::Program;
+use super::ProgramState;
+use super::graph::Target;
+use super::graph::target;
+
+// Runs still going after this many steps are taken not to terminate
+const MAX_STEPS : usize = 100_000;
+
+// A single changed instruction that makes a program terminate
+#[derive(Clone)]
+pub struct Repair {
+    pub index : usize,
+    pub instruction : Instruction,
+    // The accumulator once the repaired program has finished
+    pub accumulator : i64
+}
+
+// Changed instructions, by index, that together make a program terminate
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patch {
+    pub changes : Vec<(usize, Instruction)>,
+    pub accumulator : i64
+}
+
+// Which changes a repair search may make and how hard it tries
+#[derive(Clone, Debug)]
+pub struct RepairSearch {
+    max_changes : usize,
+    max_steps : usize,
+    flips : bool,
+    nops : bool
+}
+
+impl RepairSearch {
+    pub fn new() -> RepairSearch {
+        RepairSearch { max_changes : 2, max_steps : MAX_STEPS, flips : true, nops : true }
+    }
+
+    pub fn max_changes(mut self : Self, max_changes : usize) -> RepairSearch {
+        self.max_changes = max_changes;
+        self
+    }
+
+    // A run still going after this many steps counts as not terminating
+    pub fn max_steps(mut self : Self, max_steps : usize) -> RepairSearch {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn flips(mut self : Self, flips : bool) -> RepairSearch {
+        self.flips = flips;
+        self
+    }
+
+    pub fn nops(mut self : Self, nops : bool) -> RepairSearch {
+        self.nops = nops;
+        self
+    }
+
+    fn alternatives(self : &Self, instruction : &Instruction) -> Vec<Instruction> {
+        let mut alternatives = Vec::new();
+        let flipped = instruction.flipped();
+        if self.flips {
+            alternatives.extend(flipped.clone());
+        }
+        // Flipping to nop already stops the instruction doing anything
+        let flips_to_nop = flipped.map(|flipped| flipped.name() == NOP.name()).unwrap_or(false);
+        if self.nops && instruction.name() != NOP.name() && !(self.flips && flips_to_nop) {
+            alternatives.push(Instruction::new(&NOP, vec!(Operand::Immediate(0))));
+        }
+        alternatives
+    }
+
+    // The accumulator if the changed program terminates, and every
+    // instruction the run went through
+    fn attempt(
+        self : &Self,
+        program : &Program,
+        changes : &BTreeMap<usize, Instruction>
+    ) -> (Option<i64>, BTreeSet<usize>) {
+        let overrides : HashMap<usize, Instruction> = changes.clone().into_iter().collect();
+        let mut state = ProgramState::new(program, overrides);
+        while !state.stopped() && !state.looped() && state.steps < self.max_steps {
+            state.run_once();
+        }
+        let visited = state.instructions.iter().map(|(index, _)| *index).collect();
+        if state.at_end() {
+            (Some(state.machine.accumulator()), visited)
+        } else {
+            (None, visited)
+        }
+    }
+
+    // Only instructions the changed program still runs can be worth
+    // changing, any other change could be dropped from a repair
+    fn extend(
+        self : &Self,
+        program : &Program,
+        changes : &mut BTreeMap<usize, Instruction>,
+        remaining : usize,
+        found : &mut BTreeMap<Vec<(usize, String)>, Patch>
+    ) {
+        let (accumulator, visited) = self.attempt(program, changes);
+        if let Some(accumulator) = accumulator {
+            if remaining == 0 {
+                let key = changes.iter().map(
+                    |(index, instruction)| (*index, instruction.to_string())
+                ).collect();
+                let patch = Patch {
+                    changes : changes.iter().map(|(index, instruction)| (*index, instruction.clone())).collect(),
+                    accumulator : accumulator
+                };
+                found.insert(key, patch);
+            }
+            return;
+        }
+        if remaining == 0 {
+            return;
+        }
+        for index in visited {
+            if changes.contains_key(&index) {
+                continue;
+            }
+            for alternative in self.alternatives(&program.instructions[index]) {
+                changes.insert(index, alternative);
+                self.extend(program, changes, remaining - 1, found);
+                changes.remove(&index);
+            }
+        }
+    }
+
+    // Every repair with the fewest changes, deepening one change at a time
+    pub fn search(self : &Self, program : &Program) -> Vec<Patch> {
+        let mut found = BTreeMap::new();
+        for count in 0..=self.max_changes {
+            self.extend(program, &mut BTreeMap::new(), count, &mut found);
+            if !found.is_empty() {
+                break;
+            }
+        }
+        found.into_values().collect()
+    }
+}
+
+impl Program {
+    pub fn repairs(self : &Self, search : &RepairSearch) -> Vec<Patch> {
+        search.search(self)
+    }
+
+    fn repair_with(self : &Self, index : usize, instruction : Instruction) -> Option<Repair> {
+        let mut overrides = HashMap::new();
+        overrides.insert(index, instruction.clone());
+        let result = self.run_override(overrides, MAX_STEPS);
+        if result.terminated() {
+            Some(Repair { index : index, instruction : instruction, accumulator : result.accumulator() })
+        } else {
+            None
+        }
+    }
+
+    // Try each flip in turn, needed once jumps depend on the registers
+    fn fix_by_rerunning(self : &Self) -> Option<Repair> {
+        if self.execute_with_limit(MAX_STEPS).terminated() {
+            return None;
+        }
+        (0..self.len()).find_map(
+            |index| self.instructions[index].flipped().and_then(
+                |flipped| self.repair_with(index, flipped)
+            )
+        )
+    }
+
+    // Follow the looping program once, the flip to make is the first one that
+    // lands on an instruction which already runs through to the end
+    fn fix_linear(self : &Self) -> Option<Repair> {
+        let len = self.len();
+        let reaches = self.control_flow().terminating();
+        if reaches.first().cloned().unwrap_or(true) {
+            return None;
+        }
+        let lands_at_end = |target : Target| match target {
+            Target::End => true,
+            Target::Index(next) => reaches[next],
+            Target::Outside => false
+        };
+        let mut visited : HashSet<usize> = HashSet::new();
+        let mut counter = 0;
+        while counter < len && visited.insert(counter) {
+            let instruction = &self.instructions[counter];
+            if let Some(flipped) = instruction.flipped() {
+                if lands_at_end(target(counter, flipped.control(), len)) {
+                    return self.repair_with(counter, flipped);
+                }
+            }
+            counter = match target(counter, instruction.control(), len) {
+                Target::Index(next) => next,
+                // Already terminates, or leaves the program, nothing to flip
+                Target::End | Target::Outside => return None
+            };
+        }
+        None
+    }
+
+    // The single nop/jmp flip that makes a looping program terminate
+    pub fn fix(self : &Self) -> Option<Repair> {
+        if self.has_branches() {
+            self.fix_by_rerunning()
+        } else {
+            self.fix_linear()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepairSearch;
+    use crate::cpu::Program;
+    use crate::cpu::fixtures;
+    use crate::cpu::fixtures::extended_program;
+    use crate::cpu::fixtures::program;
+
+    #[test]
+    fn example() {
+        let program = fixtures::example();
+        let repair = program.fix().unwrap();
+        assert_eq!((repair.index, repair.instruction.to_string()), (7, "nop -4".to_string()));
+        assert_eq!(repair.accumulator, 8);
+        assert_eq!(program.fix_by_rerunning().unwrap().index, 7);
+    }
+
+    #[test]
+    fn flip_nop() {
+        let program = program("nop +3\nacc +1\njmp -2\nacc +5");
+        let repair = program.fix().unwrap();
+        assert_eq!((repair.index, repair.accumulator), (0, 5));
+    }
+
+    #[test]
+    fn nothing_to_fix() {
+        assert!(program("acc +1\nnop +0").fix().is_none());
+        assert!(program("acc +1\njmp -1\njmp +0").fix().is_none());
+        // Already terminates, though flipping the nop would too
+        let terminating = program("nop +1\nacc +1");
+        assert!(terminating.fix_linear().is_none());
+        assert!(terminating.fix_by_rerunning().is_none());
+        assert!(program("").fix_linear().is_none());
+        assert!(program("").fix_by_rerunning().is_none());
+    }
+
+    #[test]
+    fn with_branches() {
+        let program = extended_program("set a 1\njnz a +2\njmp -2\njmp -3\nacc +7");
+        let repair = program.fix().unwrap();
+        assert_eq!((repair.index, repair.accumulator), (3, 7));
+    }
+
+    #[test]
+    fn never_repeating() {
+        // Neither the program nor any flip of it repeats a state
+        assert!(extended_program("add a 1\njnz a -1\nacc +9").fix().is_none());
+    }
+
+    fn summary(program : &Program, search : &RepairSearch) -> Vec<(Vec<(usize, String)>, i64)> {
+        program.repairs(search).iter().map(
+            |patch| (
+                patch.changes.iter().map(|(index, instruction)| (*index, instruction.to_string())).collect(),
+                patch.accumulator
+            )
+        ).collect()
+    }
+
+    #[test]
+    fn single_fault() {
+        let example = fixtures::example();
+        assert_eq!(summary(&example, &RepairSearch::new()), vec!((vec!((7, "nop -4".to_string())), 8)));
+        let either = program("nop +2\njmp +0\nacc +1");
+        assert_eq!(summary(&either, &RepairSearch::new()), vec!(
+            (vec!((0, "jmp +2".to_string())), 1),
+            (vec!((1, "nop +0".to_string())), 1)
+        ));
+        assert_eq!(summary(&program("acc +1"), &RepairSearch::new()), vec!((vec!(), 1)));
+    }
+
+    #[test]
+    fn multiple_faults() {
+        let looping = program("jmp +0\njmp +0\nacc +3");
+        assert!(looping.repairs(&RepairSearch::new().max_changes(1)).is_empty());
+        assert_eq!(summary(&looping, &RepairSearch::new()), vec!(
+            (vec!((0, "nop +0".to_string()), (1, "nop +0".to_string())), 3)
+        ));
+    }
+
+    #[test]
+    fn nops() {
+        let jumping = program("jmp +0\nacc +2");
+        assert_eq!(summary(&jumping, &RepairSearch::new().flips(false)), vec!((vec!((0, "nop +0".to_string())), 2)));
+        assert!(jumping.repairs(&RepairSearch::new().flips(false).nops(false)).is_empty());
+        let branching = extended_program("set a 1\njnz a +0\nacc +4");
+        assert_eq!(summary(&branching, &RepairSearch::new()), vec!(
+            (vec!((0, "nop +0".to_string())), 4),
+            (vec!((1, "nop +0".to_string())), 4)
+        ));
+    }
+
+    #[test]
+    fn step_limit() {
+        // Counts up forever without repeating a state
+        let counting = extended_program("add a 1\njnz a -1\nacc +9");
+        assert_eq!(summary(&counting, &RepairSearch::new().max_steps(1000)), vec!(
+            (vec!((0, "nop +0".to_string())), 9),
+            (vec!((1, "nop +0".to_string())), 9)
+        ));
+    }
+}
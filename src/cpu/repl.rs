@@ -0,0 +1,171 @@
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+use super::Debugger;
+use super::Event;
+use super::Program;
+use super::REGISTERS;
+
+const HELP : &str = "\
+commands:
+  s, step [n]      run n instructions (default 1)
+  c, continue      run to a breakpoint, watch, loop, the step limit or the end
+  b, break <n>     stop before instruction n
+  d, delete <n>    remove the breakpoint on instruction n
+  w, watch         toggle stopping when the accumulator changes
+  p, print         show the next instruction and the registers
+  v, visited       list the instructions run so far
+  t, trace [n]     show the last n instructions run (default 10)
+  h, help          show this message
+  q, quit          leave the debugger";
+
+fn describe(debugger : &Debugger, program : &Program, event : &Event) -> String {
+    let listing = |index : usize| program.instruction(index).map(
+        |instruction| instruction.to_string()
+    ).unwrap_or_default();
+    match event {
+        Event::Stepped(index) => format!(
+            "{:>5}  {:<10} acc = {}", index, listing(*index), debugger.accumulator()
+        ),
+        Event::Breakpoint(index) => format!("breakpoint at {}: {}", index, listing(*index)),
+        Event::Watch { index, from, to } => format!(
+            "{:>5}  {:<10} acc {} -> {}", index, listing(*index), from, to
+        ),
+        Event::Looped(index) => format!(
+            "looped: instruction {} would run again, acc = {}", index, debugger.accumulator()
+        ),
+        Event::OutOfBounds { index, target } => format!(
+            "out of bounds: {} {} jumped to {}, acc = {}", index, listing(*index), target, debugger.accumulator()
+        ),
+        Event::Exhausted(index) => format!(
+            "out of steps: stopped before {} {}, acc = {}", index, listing(*index), debugger.accumulator()
+        ),
+        Event::Terminated => format!("terminated, acc = {}", debugger.accumulator())
+    }
+}
+
+fn print_state<W : Write>(debugger : &Debugger, output : &mut W) -> io::Result<()> {
+    match debugger.current() {
+        Some(instruction) => writeln!(output, "next {}: {}", debugger.counter(), instruction)?,
+        None => writeln!(output, "finished at {}", debugger.counter())?
+    }
+    let registers : Vec<String> = REGISTERS.iter().zip(debugger.machine().registers()).map(
+        |(name, value)| format!("{} = {}", name, value)
+    ).collect();
+    writeln!(output, "{}", registers.join(", "))?;
+    if !debugger.machine().output().is_empty() {
+        writeln!(output, "output {:?}", debugger.machine().output())?;
+    }
+    Ok(())
+}
+
+fn parse_count(arg : Option<&str>, default : usize) -> Option<usize> {
+    match arg {
+        Some(arg) => arg.parse::<usize>().ok(),
+        None => Some(default)
+    }
+}
+
+// Read commands from input until quit or end of input, writing to output
+pub fn repl<R : BufRead, W : Write>(program : &Program, input : R, output : &mut W) -> io::Result<()> {
+    let mut debugger = Debugger::new(program);
+    debugger.set_tracing(true);
+    write!(output, "(cpu) ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let arg = words.next();
+        match (command, arg) {
+            ("", _) => (),
+            ("s", _) | ("step", _) => match parse_count(arg, 1) {
+                Some(count) => for _ in 0..count {
+                    let event = debugger.step();
+                    writeln!(output, "{}", describe(&debugger, program, &event))?;
+                    if debugger.finished() || matches!(event, Event::Looped(_) | Event::Exhausted(_)) {
+                        break;
+                    }
+                },
+                None => writeln!(output, "expected a number of steps")?
+            },
+            ("c", _) | ("continue", _) => {
+                let event = debugger.run();
+                writeln!(output, "{}", describe(&debugger, program, &event))?;
+            },
+            ("b", Some(index)) | ("break", Some(index)) => match index.parse::<usize>() {
+                Ok(index) if index < program.len() => {
+                    debugger.add_breakpoint(index);
+                    writeln!(output, "breakpoint at {}", index)?;
+                },
+                _ => writeln!(output, "expected an instruction index below {}", program.len())?
+            },
+            ("d", Some(index)) | ("delete", Some(index)) => {
+                let removed = index.parse::<usize>().map(
+                    |index| debugger.remove_breakpoint(index)
+                ).unwrap_or(false);
+                if !removed {
+                    writeln!(output, "no breakpoint at {}", index)?;
+                }
+            },
+            ("b", None) | ("break", None) => writeln!(output, "breakpoints {:?}", debugger.breakpoints())?,
+            ("w", _) | ("watch", _) => {
+                let watching = !debugger.watching();
+                debugger.watch_accumulator(watching);
+                writeln!(output, "watching the accumulator: {}", if watching { "on" } else { "off" })?;
+            },
+            ("p", _) | ("print", _) => print_state(&debugger, output)?,
+            ("v", _) | ("visited", _) => writeln!(output, "{:?}", debugger.visited())?,
+            ("t", _) | ("trace", _) => match parse_count(arg, 10) {
+                Some(count) => {
+                    let trace = debugger.trace();
+                    for entry in trace.iter().skip(trace.len().saturating_sub(count)) {
+                        writeln!(
+                            output, "{:>5}  {:<10} acc = {}",
+                            entry.index, entry.instruction.to_string(), entry.accumulator
+                        )?;
+                    }
+                },
+                None => writeln!(output, "expected a number of entries")?
+            },
+            ("h", _) | ("help", _) => writeln!(output, "{}", HELP)?,
+            ("q", _) | ("quit", _) => return Ok(()),
+            _ => writeln!(output, "unknown command '{}', try help", command)?
+        }
+        write!(output, "(cpu) ")?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::repl;
+    use crate::cpu::fixtures::example;
+
+    fn session(commands : &str) -> String {
+        let program = example();
+        let mut output : Vec<u8> = Vec::new();
+        repl(&program, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        let output = session("b 4\nc\np\nc\nt 2\nq\n");
+        let lines : Vec<&str> = output.split("(cpu) ").map(str::trim_end).collect();
+        assert_eq!(lines[1], "breakpoint at 4");
+        assert_eq!(lines[2], "breakpoint at 4: jmp -3");
+        assert_eq!(lines[3], "next 4: jmp -3\nacc = 5, a = 0, b = 0, c = 0, d = 0");
+        assert_eq!(lines[4], "looped: instruction 1 would run again, acc = 5");
+        assert_eq!(lines[5], "    3  acc +3     acc = 5\n    4  jmp -3     acc = 5");
+    }
+
+    #[test]
+    fn bad_commands() {
+        let output = session("b 99\nfoo\n");
+        assert!(output.contains("expected an instruction index below 9"));
+        assert!(output.contains("unknown command 'foo'"));
+    }
+}
@@ -1,24 +1,44 @@
 use std::collections::HashSet;
 
+use super::error::ParseError;
+
+fn parse_deck<'a, I>(lines : I) -> Result<Vec<usize>, ParseError>
+  where I : Iterator<Item = &'a str>
+{
+    let lines : Vec<&str> = lines.collect();
+    let num = lines.len();
+    lines.into_iter().enumerate().filter(
+        |(_, line)| !line.starts_with("Player ")
+    ).map(
+        |(index, line)| line.parse::<usize>().map_err(
+            |_| ParseError::new(1, "expected a card").offset_lines(num - 1 - index)
+        )
+    ).collect()
+}
+
+#[derive(Clone)]
 pub struct Game {
     player1 : Vec<usize>,
     player2 : Vec<usize>
 }
 
 impl Game {
-    pub fn from_lines<'a,I>(lines1 : I, lines2 : I) -> Game 
-      where I : Iterator<Item = &'a str>
-    {
-        Game {
-            player1 : lines1.filter_map(
-                |item| item.parse::<usize>().ok()
-            ).collect(),
-            player2 : lines2.filter_map(
-                |item| item.parse::<usize>().ok()
-            ).collect()
+    // Both players' decks separated by a blank line, each with a "Player N:" header
+    pub fn from_string(string : &str) -> Result<Game, ParseError> {
+        let players : Vec<&str> = string.split("\n\n").collect();
+        if players.len() != 2 {
+            return Err(ParseError::new(1, "expected two players' decks"));
         }
+        let player2_start = players[0].lines().count() + 1;
+        Ok(Game {
+            player1 : parse_deck(players[0].lines().rev())?,
+            player2 : parse_deck(players[1].lines().rev()).map_err(
+                |err| err.offset_lines(player2_start)
+            )?
+        })
     }
 
+    #[allow(clippy::ptr_arg)]
     pub fn from_players(player1 : &Vec<usize>, num1 : usize, player2 : &Vec<usize>, num2 : usize) -> Game 
     {
         Game {
@@ -41,10 +61,12 @@ impl Game {
         }
     }
 
+    #[allow(clippy::len_zero)]
     pub fn player1win(self : &Self) -> bool {
         self.player1.len() !=0
     }
 
+    #[allow(clippy::needless_return)]
     pub fn play_recursive(self : &mut Self) -> bool {
         let mut previous : HashSet<(Vec<usize>, Vec<usize>)> = HashSet::new();
         while !self.player1.is_empty() && !self.player2.is_empty() {
@@ -87,4 +109,39 @@ impl Game {
             ).sum()
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Game;
+
+    const EXAMPLE : &str = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10";
+
+    #[test]
+    fn play() {
+        let mut game = Game::from_string(EXAMPLE).unwrap();
+        game.play();
+        assert!(!game.player1win());
+        assert_eq!(game.score(), 306);
+    }
+
+    #[test]
+    fn play_recursive() {
+        let mut game = Game::from_string(EXAMPLE).unwrap();
+        game.play_recursive();
+        assert_eq!(game.score(), 291);
+    }
+
+    #[test]
+    fn infinite_game_ends() {
+        // Repeating a previous round gives the game to player 1
+        let mut game = Game::from_string("Player 1:\n43\n19\n\nPlayer 2:\n2\n29\n14").unwrap();
+        assert!(game.play_recursive());
+    }
+
+    #[test]
+    fn malformed() {
+        let err = Game::from_string("Player 1:\n9\n2\n\nPlayer 2:\n5\nx").err().unwrap();
+        assert_eq!((err.line, err.column), (7, 1));
+    }
+}
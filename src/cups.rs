@@ -103,4 +103,30 @@ impl Cups {
         }
         val
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cups;
+
+    fn cups() -> Cups {
+        Cups::from_cycle_ints(vec!(3, 8, 9, 1, 2, 5, 4, 6, 7))
+    }
+
+    #[test]
+    fn labels() {
+        let mut data = cups();
+        data.simulate(10);
+        assert_eq!(data.labels(1), "92658374");
+        let mut data = cups();
+        data.simulate(100);
+        assert_eq!(data.labels(1), "67384529");
+    }
+
+    #[test]
+    fn offset_labels() {
+        let mut data = cups();
+        data.simulate(10);
+        assert_eq!((data.offset_labels(1, 1), data.offset_labels(1, 2)), (9, 2));
+    }
+}
@@ -1,21 +1,27 @@
 use std::collections::HashMap;
 
+use super::error::ParseError;
+
 pub struct Form {
     people : usize,
     answers : HashMap<char, usize>
 }
 impl Form {
-    pub fn new(string : &str) -> Form {
-        let chars = string.chars().filter(
-            |ch| ch.is_ascii_alphabetic()
-        );
+    pub fn from_string(string : &str) -> Result<Form, ParseError> {
         let mut answers = HashMap::new();
-        for ch in chars {
-            let counter = answers.entry(ch).or_insert(0);
-            *counter += 1;
+        for (i, line) in string.lines().enumerate() {
+            for (j, ch) in line.chars().enumerate() {
+                if !ch.is_ascii_lowercase() {
+                    return Err(
+                        ParseError::new(j + 1, "expected a question letter").offset_lines(i)
+                    );
+                }
+                let counter = answers.entry(ch).or_insert(0);
+                *counter += 1;
+            }
         }
         let people = string.lines().count();
-        Form { people : people, answers : answers }
+        Ok(Form { people : people, answers : answers })
     }
     pub fn num_any(self : &Self) -> usize {
         self.answers.values().filter(
@@ -27,4 +33,28 @@ impl Form {
             |&&val| val == self.people
         ).count()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Form;
+
+    const EXAMPLE : &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
+
+    #[test]
+    fn example() {
+        let forms : Vec<Form> = EXAMPLE.split("\n\n").map(
+            |group| Form::from_string(group).unwrap()
+        ).collect();
+        let any : usize = forms.iter().map(|form| form.num_any()).sum();
+        let all : usize = forms.iter().map(|form| form.num_all()).sum();
+        assert_eq!(any, 11);
+        assert_eq!(all, 6);
+    }
+
+    #[test]
+    fn malformed() {
+        let err = Form::from_string("ab\naB").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 2));
+    }
+}
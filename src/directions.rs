@@ -1,3 +1,5 @@
+use super::error::ParseError;
+
 #[derive(Copy, Clone, Debug)]
 enum Cardinal {
     North,
@@ -53,19 +55,21 @@ impl ActionType {
         }
     }
 }
+#[derive(Clone)]
 pub struct Action {
     atype : ActionType,
     val : i32
 }
 impl Action {
-    pub fn from_string(string : &str) -> Option<Action> {
-        ActionType::from_char(
-            string.chars().next().unwrap()
-        ).and_then(
-            |atype| string[1..].parse::<i32>().ok().map(
-                |val| Action{ atype : atype, val : val}
-            )
-        )
+    pub fn from_string(string : &str) -> Result<Action, ParseError> {
+        let mut chars = string.chars();
+        let atype = chars.next().and_then(ActionType::from_char).ok_or(
+            ParseError::new(1, "expected an action of N, S, E, W, L, R or F")
+        )?;
+        let val = chars.as_str().parse::<i32>().map_err(
+            |_| ParseError::new(2, "expected an amount")
+        )?;
+        Ok(Action{ atype : atype, val : val})
     }
 }
 pub struct Ship {
@@ -129,6 +133,7 @@ impl Ship {
             Cardinal::West => self.waypoint.0 -= distance
         };
     }
+    #[allow(clippy::assign_op_pattern)]
     fn move_to_waypoint(self : &mut Self, distance : i32) {
         self.position.0 = self.position.0 + (self.waypoint.0 * distance);
         self.position.1 = self.position.1 + (self.waypoint.1 * distance);
@@ -151,4 +156,43 @@ impl Ship {
     pub fn distance(self : &Self) -> u32 {
         self.position.0.unsigned_abs() + self.position.1.unsigned_abs()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Action;
+    use super::Ship;
+
+    fn actions(text : &str) -> Vec<Action> {
+        text.lines().map(
+            |line| Action::from_string(line).unwrap()
+        ).collect()
+    }
+
+    #[test]
+    fn example() {
+        let data = actions("F10\nN3\nF7\nR90\nF11");
+        let mut ship = Ship::new();
+        ship.simulate(data.iter().cloned());
+        assert_eq!(ship.distance(), 25);
+        let mut ship = Ship::new();
+        ship.simulate_waypoint(data.iter().cloned());
+        assert_eq!(ship.distance(), 286);
+    }
+
+    #[test]
+    fn distance_from_negative_position() {
+        let mut ship = Ship::new();
+        ship.simulate(actions("S5\nW3").into_iter());
+        assert_eq!(ship.distance(), 8);
+        let mut ship = Ship::new();
+        ship.simulate(actions(&format!("W{}", i32::MAX)).into_iter());
+        assert_eq!(ship.distance(), i32::MAX as u32);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Action::from_string("X10").err().unwrap().column, 1);
+        assert_eq!(Action::from_string("F").err().unwrap().column, 2);
+    }
+}
@@ -1,74 +1,257 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::error;
+use std::fmt;
 
-#[derive(Clone)]
-struct Mask {
-    mask : String,
+use super::error::ParseError;
+use super::error::parse_lines;
+use super::json as json;
+
+pub const DEFAULT_WIDTH : usize = 36;
+// Wider words could have more addresses in a pattern than a u64 counts
+pub const MAX_WIDTH : usize = 63;
+
+// Bit i of each field is set when character width - 1 - i of the mask is
+// '1', '0' or 'X' respectively
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    ones : u64,
+    zeros : u64,
+    floating : u64,
+    width : usize
+}
+
+// A program that does not fit the word width of a computer, lines are 1 based
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DockingError {
+    UnsupportedWidth(usize),
+    MaskWidth { line : usize, found : usize, expected : usize },
+    Overflow { line : usize, value : u64, width : usize }
+}
+
+// A set of addresses, those matching fixed everywhere except the floating
+// bits, which are clear in fixed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    fixed : u64,
+    floating : u64
+}
+
+// Every address of a pattern, walking the subsets of its floating bits
+pub struct Addresses {
+    base : u64,
+    floating : u64,
+    subset : Option<u64>
+}
+
+// Every written address and its value, lowest address first, merging the
+// ascending addresses of each region
+pub struct Cells<'a> {
+    regions : &'a [(Pattern, u64)],
+    addresses : Vec<Addresses>,
+    next : BinaryHeap<Reverse<(u64, usize)>>
 }
 
-fn to_bits(num : &u64, len : usize) -> Vec<bool> {
-    (0..len).map(
-      |i|  (num >> (len-1-i)) % 2 == 1
-    ).collect()
+// One assignment as it ran, with the mask in effect at the time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Write {
+    pub line : usize,
+    pub mask : Mask,
+    pub addresses : Pattern,
+    pub value : u64
 }
 
-fn mask_bit((ch, bit) : (char, &bool)) -> char {
-    match ch {
-        '0' => '0',
-        '1' => '1',
-        _ => if *bit { '1' } else { '0' }
+// Writes kept as disjoint patterns, a later write takes its addresses out of
+// every earlier one so nothing is counted twice
+#[derive(Clone, Debug, Default)]
+pub struct Memory {
+    regions : Vec<(Pattern, u64)>
+}
+
+impl Iterator for Addresses {
+    type Item = u64;
+
+    fn next(self : &mut Self) -> Option<u64> {
+        let subset = self.subset?;
+        self.subset = if subset == self.floating {
+            None
+        } else {
+            Some(subset.wrapping_sub(self.floating) & self.floating)
+        };
+        Some(self.base | subset)
     }
 }
 
-fn one_mask_bit((ch, bit) : (char, &bool)) -> char {
-    match ch {
-        '1' => '1',
-        'X' => '0',
-        _ => if *bit { '1' } else { '0' }
+impl<'a> Iterator for Cells<'a> {
+    type Item = (u64, u64);
+
+    fn next(self : &mut Self) -> Option<(u64, u64)> {
+        let Reverse((address, region)) = self.next.pop()?;
+        if let Some(following) = self.addresses[region].next() {
+            self.next.push(Reverse((following, region)));
+        }
+        Some((address, self.regions[region].1))
     }
 }
 
-fn make_floating(num : u64, index : &[usize]) -> Vec<u64> {
-    if index.len() == 0 {
-        return vec!(num);
+impl Pattern {
+    pub fn address(address : u64) -> Pattern {
+        Pattern { fixed : address, floating : 0 }
+    }
+
+    pub fn new(fixed : u64, floating : u64) -> Pattern {
+        Pattern { fixed : fixed & !floating, floating : floating }
+    }
+
+    pub fn count(self : &Self) -> u64 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(self : &Self, address : u64) -> bool {
+        address & !self.floating == self.fixed
+    }
+
+    pub fn intersects(self : &Self, other : &Pattern) -> bool {
+        (self.fixed ^ other.fixed) & !self.floating & !other.floating == 0
+    }
+
+    // Width bits with X for the floating ones, written like a mask so fixed
+    // and floating addresses read the same way
+    pub fn format(self : &Self, width : usize) -> String {
+        (0..width).rev().map(
+            |bit| match (self.floating >> bit & 1, self.fixed >> bit & 1) {
+                (1, _) => 'X',
+                (_, 1) => '1',
+                _ => '0'
+            }
+        ).collect()
+    }
+
+    pub fn addresses(self : &Self) -> Addresses {
+        Addresses { base : self.fixed, floating : self.floating, subset : Some(0) }
+    }
+
+    // Disjoint patterns covering the addresses of self missing from other,
+    // one for each bit floating in self but fixed in other
+    pub fn subtract(self : &Self, other : &Pattern) -> Vec<Pattern> {
+        if !self.intersects(other) {
+            return vec!(*self);
+        }
+        let mut pieces = Vec::new();
+        let mut rest = *self;
+        let mut split = self.floating & !other.floating;
+        while split != 0 {
+            let bit = split & split.wrapping_neg();
+            split &= !bit;
+            rest.floating &= !bit;
+            pieces.push(Pattern { fixed : rest.fixed | (!other.fixed & bit), floating : rest.floating });
+            rest.fixed |= other.fixed & bit;
+        }
+        pieces
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+
+    pub fn write(self : &mut Self, pattern : Pattern, value : u64) {
+        let mut regions = Vec::with_capacity(self.regions.len() + 1);
+        for (region, old) in self.regions.drain(..) {
+            if region.intersects(&pattern) {
+                regions.extend(region.subtract(&pattern).into_iter().map(|piece| (piece, old)));
+            } else {
+                regions.push((region, old));
+            }
+        }
+        regions.push((pattern, value));
+        self.regions = regions;
+    }
+
+    pub fn get(self : &Self, address : u64) -> Option<u64> {
+        self.regions.iter().find(
+            |(region, _)| region.contains(address)
+        ).map(
+            |(_, value)| *value
+        )
+    }
+
+    pub fn regions(self : &Self) -> &[(Pattern, u64)] {
+        &self.regions
+    }
+
+    // Regions ordered by their lowest address
+    pub fn sorted_regions(self : &Self) -> Vec<(Pattern, u64)> {
+        let mut regions = self.regions.clone();
+        regions.sort_by_key(|(region, _)| region.fixed);
+        regions
+    }
+
+    pub fn iter(self : &Self) -> Cells<'_> {
+        let mut addresses : Vec<Addresses> = self.regions.iter().map(
+            |(region, _)| region.addresses()
+        ).collect();
+        let next = addresses.iter_mut().enumerate().filter_map(
+            |(index, region)| region.next().map(|address| Reverse((address, index)))
+        ).collect();
+        Cells { regions : &self.regions, addresses : addresses, next : next }
+    }
+
+    // A region can hold up to 2^63 cells of up to 2^63 each, so sum in u128
+    pub fn sum(self : &Self) -> u128 {
+        self.regions.iter().map(
+            |(region, value)| region.count() as u128 * *value as u128
+        ).sum()
     }
-    let mask : u64 = u64::pow(2,index[0] as u32);
-    let mut base = make_floating(num, &index[1..]);
-    base.extend(make_floating(num + mask, &index[1..]));
-    return base;
 }
 
 impl Mask {
 
-    pub fn empty() -> Mask {
+    pub fn empty(width : usize) -> Mask {
         Mask {
-            mask : "00000000000000000000000000000000".to_string()
+            ones : 0,
+            zeros : low_bits(width),
+            floating : 0,
+            width : width
         }
     }
-    pub fn from_string(string : &str) -> Mask {
-        Mask {
-            mask : string.to_string()
+    pub fn from_string(string : &str) -> Result<Mask, ParseError> {
+        let width = string.chars().count();
+        if width == 0 || width > MAX_WIDTH {
+            return Err(ParseError::new(1, &format!("expected a mask of 1 to {} bits", MAX_WIDTH)));
+        }
+        let mut mask = Mask { ones : 0, zeros : 0, floating : 0, width : width };
+        for (index, (offset, ch)) in string.char_indices().enumerate() {
+            let bit = 1 << (width - 1 - index);
+            match ch {
+                '1' => mask.ones |= bit,
+                '0' => mask.zeros |= bit,
+                'X' => mask.floating |= bit,
+                _ => return Err(ParseError::at(string, &string[offset..], "expected '0', '1' or 'X'"))
+            }
         }
+        Ok(mask)
+    }
+    pub fn width(self : &Self) -> usize {
+        self.width
     }
-    pub fn mask(self : &Self, num : &u64) -> u64 {
-        let bits = to_bits(num, 36);
-        let masked : String 
-          = self.mask.chars().zip(bits.iter()).map(mask_bit).collect();
-        let res = u64::from_str_radix(&masked, 2).expect("Bad form");
-        res
+    pub fn mask(self : &Self, num : u64) -> u64 {
+        (num & self.floating) | self.ones
     }
 
-    pub fn decode(self : &Self, num : &u64) -> Vec<u64> {
-        let bits = to_bits(num, 36);
-        let masked : String
-          = self.mask.chars().zip(bits.iter()).map(one_mask_bit).collect();
-        let base = u64::from_str_radix(&masked, 2).expect("Bad form");
-        let indices : Vec<usize> = self.mask.chars().enumerate().filter(
-            |(_,ch)| *ch == 'X'
-        ).map(
-            |(i,_)| 35 - i
-        ).collect();
-        make_floating(base, &indices)
+    // Every address written to when num is decoded by the mask
+    pub fn floating_address(self : &Self, num : u64) -> Pattern {
+        Pattern::new((num & self.zeros) | self.ones, self.floating)
     }
+
+    pub fn decode(self : &Self, num : u64) -> Addresses {
+        self.floating_address(num).addresses()
+    }
+}
+
+fn low_bits(width : usize) -> u64 {
+    (1 << width) - 1
 }
 
 enum Expression {
@@ -77,27 +260,36 @@ enum Expression {
 }
 
 impl Expression {
-    fn from_line(line : &str) -> Option<Expression> {
-        Expression::from_line_mask(line).or(
-            Expression::from_line_assign(line)
-        )
+    fn from_line(line : &str) -> Result<Expression, ParseError> {
+        if let Some(rem) = line.strip_prefix("mask = ") {
+            Expression::from_line_mask(rem).map_err(
+                |err| err.within(line, rem)
+            )
+        } else if let Some(rem) = line.strip_prefix("mem[") {
+            Expression::from_line_assign(rem).map_err(
+                |err| err.within(line, rem)
+            )
+        } else {
+            Err(ParseError::new(1, "expected 'mask = ' or 'mem['"))
+        }
     }
 
-    fn from_line_mask(line : &str) -> Option<Expression> {
-        line.strip_prefix("mask = ").map(
-            |rem| Expression::SetMask(Mask::from_string(rem))
-        )
+    fn from_line_mask(rem : &str) -> Result<Expression, ParseError> {
+        Ok(Expression::SetMask(Mask::from_string(rem)?))
     }
 
-    fn from_line_assign(line : &str) -> Option<Expression> {
-        line.strip_prefix("mem[").and_then(
-            |rem| rem.split_once("] = ").map(
-                |(index, val)| Expression::AssignVar(
-                    index.parse::<u64>().expect("Badline"),
-                    val.parse::<u64>().expect("Badline"),
-                )
-            )
-        )
+    fn from_line_assign(rem : &str) -> Result<Expression, ParseError> {
+        let (index, val) = rem.split_once("] = ").ok_or(
+            ParseError::new(1, "expected '<address>] = <value>'")
+        )?;
+        Ok(Expression::AssignVar(
+            index.parse::<u64>().map_err(
+                |_| ParseError::at(rem, index, "expected an address")
+            )?,
+            val.parse::<u64>().map_err(
+                |_| ParseError::at(rem, val, "expected a value")
+            )?
+        ))
     }
 }
 
@@ -106,66 +298,399 @@ pub struct Program {
 }
 
 impl Program {
-    pub fn from_lines<I>(lines : I) -> Program
+    pub fn from_lines<I>(lines : I) -> Result<Program, ParseError>
       where I : Iterator<Item = String>
     {
-        Program {
-            expressions : lines.filter_map(
-                |line| Expression::from_line(&line)
-            ).collect()
-        }
+        Ok(Program {
+            expressions : parse_lines(lines, Expression::from_line)?
+        })
     }
 }
 
 pub struct Computer {
+    width : usize,
     current_mask : Mask,
-    assignments : HashMap<u64, u64>
+    memory : Memory,
+    history : Vec<Write>
 }
 
 impl Computer {
     pub fn new() -> Computer 
     {
         Computer {
-            current_mask : Mask::empty(),
-            assignments : HashMap::new()
+            width : DEFAULT_WIDTH,
+            current_mask : Mask::empty(DEFAULT_WIDTH),
+            memory : Memory::new(),
+            history : Vec::new()
+        }
+    }
+
+    pub fn with_width(width : usize) -> Result<Computer, DockingError> {
+        if width == 0 || width > MAX_WIDTH {
+            return Err(DockingError::UnsupportedWidth(width));
+        }
+        Ok(Computer {
+            width : width,
+            current_mask : Mask::empty(width),
+            memory : Memory::new(),
+            history : Vec::new()
+        })
+    }
+
+    pub fn width(self : &Self) -> usize {
+        self.width
+    }
+
+    // Every mask has to be as wide as a word and every address and value fit in one
+    pub fn check(self : &Self, program : &Program) -> Result<(), DockingError> {
+        for (index, expression) in program.expressions.iter().enumerate() {
+            let line = index + 1;
+            match expression {
+                Expression::SetMask(mask) => if mask.width != self.width {
+                    return Err(DockingError::MaskWidth { line : line, found : mask.width, expected : self.width });
+                },
+                Expression::AssignVar(address, value) => {
+                    let overflow = [*address, *value].iter().cloned().find(
+                        |value| value & !low_bits(self.width) != 0
+                    );
+                    if let Some(value) = overflow {
+                        return Err(DockingError::Overflow { line : line, value : value, width : self.width });
+                    }
+                }
+            }
         }
+        Ok(())
+    }
+
+    fn assign(self : &mut Self, line : usize, addresses : Pattern, value : u64) {
+        self.memory.write(addresses, value);
+        self.history.push(Write {
+            line : line,
+            mask : self.current_mask,
+            addresses : addresses,
+            value : value
+        });
     }
 
-    fn run_expression(self: &mut Self, expression : &Expression) {
+    fn run_expression(self: &mut Self, line : usize, expression : &Expression) {
         match expression {
-            Expression::SetMask(mask) => self.current_mask = mask.clone(),
+            Expression::SetMask(mask) => self.current_mask = *mask,
             Expression::AssignVar(index, val) => {
-                let masked = self.current_mask.mask(val);
-                self.assignments.insert(*index, masked);
+                let masked = self.current_mask.mask(*val);
+                self.assign(line, Pattern::address(*index), masked);
             }
         }
     }
 
-    pub fn run(self: &mut Self, program : &Program) {
-        for expression in &program.expressions {
-            self.run_expression(&expression)
+    pub fn run(self: &mut Self, program : &Program) -> Result<(), DockingError> {
+        self.check(program)?;
+        for (index, expression) in program.expressions.iter().enumerate() {
+            self.run_expression(index + 1, expression)
         }
+        Ok(())
     }
 
-    fn run_decode_expression(self: &mut Self, expression : &Expression) {
+    fn run_decode_expression(self: &mut Self, line : usize, expression : &Expression) {
         match expression {
-            Expression::SetMask(mask) => self.current_mask = mask.clone(),
+            Expression::SetMask(mask) => self.current_mask = *mask,
             Expression::AssignVar(index, val) => {
-                let indices = self.current_mask.decode(index);
-                for float_index in indices {
-                    self.assignments.insert(float_index, *val);
-                }
+                let addresses = self.current_mask.floating_address(*index);
+                self.assign(line, addresses, *val);
             }
         }
     }
 
-    pub fn run_decode(self: &mut Self, program : &Program) {
-        for expression in &program.expressions {
-            self.run_decode_expression(&expression)
+    pub fn run_decode(self: &mut Self, program : &Program) -> Result<(), DockingError> {
+        self.check(program)?;
+        for (index, expression) in program.expressions.iter().enumerate() {
+            self.run_decode_expression(index + 1, expression)
         }
+        Ok(())
+    }
+
+    pub fn sum_variables(self : &Self) -> u128 {
+        self.memory.sum()
+    }
+
+    pub fn get(self : &Self, address : u64) -> Option<u64> {
+        self.memory.get(address)
+    }
+
+    pub fn current_mask(self : &Self) -> &Mask {
+        &self.current_mask
+    }
+
+    pub fn memory(self : &Self) -> &Memory {
+        &self.memory
     }
 
-    pub fn sum_variables(self : &Self) -> u64 {
-        self.assignments.values().sum()
+    pub fn iter(self : &Self) -> Cells<'_> {
+        self.memory.iter()
     }
-}
\ No newline at end of file
+
+    pub fn writes(self : &Self) -> &[Write] {
+        &self.history
+    }
+
+    // Every write that touched address, oldest first
+    pub fn history(self : &Self, address : u64) -> Vec<&Write> {
+        self.history.iter().filter(
+            |write| write.addresses.contains(address)
+        ).collect()
+    }
+
+    // The mask then one line per region of memory, lowest address first
+    pub fn dump(self : &Self) -> String {
+        let mut lines = vec!(format!("mask = {}", self.current_mask));
+        for (region, value) in self.memory.sorted_regions() {
+            lines.push(format!("mem[{}] = {}", region.format(self.width), value));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(self : &Self) -> String {
+        // Counts and values can pass 2^53, so they are written as strings
+        let regions : Vec<String> = self.memory.sorted_regions().iter().map(
+            |(region, value)| json::object(&[
+                ("address", json::string(&region.format(self.width))),
+                ("count", json::string(&region.count().to_string())),
+                ("value", json::string(&value.to_string()))
+            ])
+        ).collect();
+        json::object(&[
+            ("width", self.width.to_string()),
+            ("mask", json::string(&self.current_mask.to_string())),
+            ("sum", json::string(&self.sum_variables().to_string())),
+            ("memory", json::array(&regions))
+        ])
+    }
+}
+
+// Written the way it is parsed, e.g. "X1XX0"
+impl fmt::Display for Mask {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        for bit in (0..self.width).rev() {
+            let ch = if self.ones >> bit & 1 == 1 {
+                '1'
+            } else if self.floating >> bit & 1 == 1 {
+                'X'
+            } else {
+                '0'
+            };
+            write!(f, "{}", ch)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DockingError {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DockingError::UnsupportedWidth(width) => write!(f, "word width {} is not between 1 and {}", width, MAX_WIDTH),
+            DockingError::MaskWidth { line, found, expected } => write!(
+                f, "line {}: mask has {} bits, expected {}", line, found, expected
+            ),
+            DockingError::Overflow { line, value, width } => write!(
+                f, "line {}: {} does not fit in {} bits", line, value, width
+            )
+        }
+    }
+}
+
+impl error::Error for DockingError {}
+
+// Problems with a program are reported against the line they are on
+impl From<DockingError> for ParseError {
+    fn from(err : DockingError) -> ParseError {
+        match err {
+            DockingError::UnsupportedWidth(_) => ParseError::new(1, &err.to_string()),
+            DockingError::MaskWidth { line, found, expected } => ParseError::new(
+                8, &format!("mask has {} bits, expected {}", found, expected)
+            ).offset_lines(line - 1),
+            DockingError::Overflow { line, value, width } => ParseError::new(
+                1, &format!("{} does not fit in {} bits", value, width)
+            ).offset_lines(line - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Computer;
+    use super::DockingError;
+    use super::Mask;
+    use super::Pattern;
+    use super::Program;
+    use crate::error::ParseError;
+
+    fn program(text : &str) -> Program {
+        Program::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn run() {
+        let program = program(
+            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11\nmem[7] = 101\nmem[8] = 0"
+        );
+        let mut cpu = Computer::new();
+        cpu.run(&program).unwrap();
+        assert_eq!(cpu.sum_variables(), 165);
+    }
+
+    #[test]
+    fn run_decode() {
+        let program = program(
+            "mask = 000000000000000000000000000000X1001X\nmem[42] = 100\n\
+             mask = 00000000000000000000000000000000X0XX\nmem[26] = 1"
+        );
+        let mut cpu = Computer::new();
+        cpu.run_decode(&program).unwrap();
+        assert_eq!(cpu.sum_variables(), 208);
+    }
+
+    #[test]
+    fn masks() {
+        let mask = Mask::from_string("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap();
+        assert_eq!((mask.mask(11), mask.mask(101), mask.mask(0)), (73, 101, 64));
+        assert_eq!(mask.mask(1 << 40 | 11), 73);
+        assert_eq!(Mask::empty(36).mask(u64::MAX), 0);
+    }
+
+    #[test]
+    fn decode() {
+        let mask = Mask::from_string("000000000000000000000000000000X1001X").unwrap();
+        let mut addresses : Vec<u64> = mask.decode(42).collect();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec!(26, 27, 58, 59));
+        let fixed = Mask::from_string("000000000000000000000000000000010010").unwrap();
+        assert_eq!(fixed.decode(1).collect::<Vec<u64>>(), vec!(19));
+        let low = Mask::from_string(&format!("{}{}", "0".repeat(26), "X".repeat(10))).unwrap();
+        assert_eq!(low.decode(1 << 20).count(), 1 << 10);
+        assert_eq!(low.decode(1 << 20).last(), Some((1 << 20) + (1 << 10) - 1));
+    }
+
+    #[test]
+    fn subtract() {
+        let all = Pattern::new(0, 0b111);
+        let pieces = all.subtract(&Pattern::address(0b101));
+        assert_eq!(pieces.iter().map(|piece| piece.count()).sum::<u64>(), 7);
+        assert!(pieces.iter().all(|piece| !piece.contains(0b101)));
+        assert!((0..8).filter(|&address| address != 0b101).all(
+            |address| pieces.iter().filter(|piece| piece.contains(address)).count() == 1
+        ));
+        assert_eq!(Pattern::address(3).subtract(&Pattern::new(0, 0b11)), vec!());
+        assert_eq!(Pattern::address(4).subtract(&Pattern::new(0, 0b11)), vec!(Pattern::address(4)));
+    }
+
+    #[test]
+    fn many_floating_bits() {
+        let program = program(
+            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 1\n\
+             mask = 0XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 2\n\
+             mask = 000000000000000000000000000000000000\nmem[5] = 0"
+        );
+        let mut cpu = Computer::new();
+        cpu.run_decode(&program).unwrap();
+        assert_eq!(cpu.sum_variables(), (1 << 35) * 3 - 2);
+        assert_eq!(cpu.memory.get(5), Some(0));
+        assert_eq!(cpu.memory.get(1 << 35), Some(1));
+        assert_eq!(cpu.memory.get(6), Some(2));
+        assert_eq!(cpu.memory.get(1 << 36), None);
+    }
+
+    #[test]
+    fn widths() {
+        let program = program("mask = X1X0\nmem[3] = 9\nmask = 0X0X\nmem[15] = 2");
+        let mut cpu = Computer::with_width(4).unwrap();
+        cpu.run(&program).unwrap();
+        assert_eq!(cpu.sum_variables(), 12);
+        let mut cpu = Computer::with_width(4).unwrap();
+        cpu.run_decode(&program).unwrap();
+        assert_eq!(cpu.sum_variables(), 9 * 3 + 2 * 4);
+        assert_eq!(Computer::with_width(64).err(), Some(DockingError::UnsupportedWidth(64)));
+        assert_eq!(Computer::new().width(), 36);
+    }
+
+    #[test]
+    fn large_sums() {
+        let mut cpu = Computer::new();
+        cpu.run_decode(&program("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 34359738368")).unwrap();
+        assert_eq!(cpu.sum_variables(), (1u128 << 36) * 34359738368);
+        let mut cpu = Computer::with_width(63).unwrap();
+        cpu.run_decode(
+            &program("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 9223372036854775807")
+        ).unwrap();
+        assert_eq!(cpu.sum_variables(), (1u128 << 63) * (i64::MAX as u128));
+    }
+
+    #[test]
+    fn width_errors() {
+        let program = program("mask = X1X0\nmem[3] = 9\nmem[16] = 2");
+        let mut cpu = Computer::new();
+        assert_eq!(cpu.run(&program).err(), Some(DockingError::MaskWidth { line : 1, found : 4, expected : 36 }));
+        let mut cpu = Computer::with_width(4).unwrap();
+        let err = cpu.run_decode(&program).err().unwrap();
+        assert_eq!(err, DockingError::Overflow { line : 3, value : 16, width : 4 });
+        assert_eq!(err.to_string(), "line 3: 16 does not fit in 4 bits");
+        let err = ParseError::from(err);
+        assert_eq!((err.line, err.column), (3, 1));
+    }
+
+    #[test]
+    fn inspect() {
+        let program = program(
+            "mask = 000000000000000000000000000000X1001X\nmem[42] = 100\n\
+             mask = 00000000000000000000000000000000X0XX\nmem[26] = 1"
+        );
+        let mut cpu = Computer::new();
+        cpu.run_decode(&program).unwrap();
+        assert_eq!(cpu.current_mask().to_string(), "00000000000000000000000000000000X0XX");
+        assert_eq!((cpu.get(58), cpu.get(26), cpu.get(5)), (Some(100), Some(1), None));
+        let cells : Vec<(u64, u64)> = cpu.iter().collect();
+        assert_eq!(cells, vec!(
+            (16, 1), (17, 1), (18, 1), (19, 1), (24, 1), (25, 1), (26, 1), (27, 1), (58, 100), (59, 100)
+        ));
+        let history : Vec<(usize, String, u64)> = cpu.history(26).iter().map(
+            |write| (write.line, write.mask.to_string(), write.value)
+        ).collect();
+        assert_eq!(history, vec!(
+            (2, "000000000000000000000000000000X1001X".to_string(), 100),
+            (4, "00000000000000000000000000000000X0XX".to_string(), 1)
+        ));
+        assert_eq!(cpu.history(58).len(), 1);
+        assert_eq!(cpu.writes().len(), 2);
+    }
+
+    #[test]
+    fn dumps() {
+        let program = program("mask = X1X0\nmem[3] = 9\nmem[1] = 2\nmask = 0X0X\nmem[15] = 2");
+        let mut cpu = Computer::with_width(4).unwrap();
+        cpu.run(&program).unwrap();
+        assert_eq!(cpu.dump(), "mask = 0X0X\nmem[0001] = 6\nmem[0011] = 12\nmem[1111] = 0");
+        let mut cpu = Computer::with_width(4).unwrap();
+        cpu.run_decode(&program).unwrap();
+        let dump = cpu.dump();
+        let lines : Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[1..].to_vec(), vec!("mem[X101] = 2", "mem[0111] = 2", "mem[1X1X] = 2"));
+        assert!(cpu.to_json().starts_with(
+            "{\"width\":4,\"mask\":\"0X0X\",\"sum\":\""
+        ));
+        assert!(cpu.to_json().contains("{\"address\":\"1X1X\",\"count\":\"4\",\"value\":\"2\"}"));
+        assert!(cpu.to_json().contains("{\"address\":\"0111\",\"count\":\"1\",\"value\":\"2\"}"));
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = vec!("mem[8] 11".to_string());
+        let err = Program::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (1, 5));
+        let lines = vec!("mem[8] = 11".to_string(), "mem[x] = 3".to_string());
+        let err = Program::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 5));
+        let lines = vec!("mask = 0X1x".to_string());
+        let err = Program::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.column, err.message.as_str()), (11, "expected '0', '1' or 'X'"));
+        let lines = vec!("mask = ".to_string());
+        assert!(Program::from_lines(lines.into_iter()).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use super::error::ParseError;
+
 pub struct Handshake {
     door_pub : u64,
     card_pub : u64
@@ -12,22 +14,22 @@ fn transform(loopnum : u64, val : u64) -> u64 {
 }
 
 impl Handshake {
-    pub fn from_strings(door : &str, card : &str) -> Handshake {
-        Handshake {
-            door_pub : door.parse::<u64>().unwrap(),
-            card_pub : card.parse::<u64>().unwrap()
-        }
+    pub fn from_strings(door : &str, card : &str) -> Result<Handshake, ParseError> {
+        Ok(Handshake {
+            door_pub : door.parse::<u64>().map_err(
+                |_| ParseError::new(1, "expected the door public key")
+            )?,
+            card_pub : card.parse::<u64>().map_err(
+                |_| ParseError::new(1, "expected the card public key").offset_lines(1)
+            )?
+        })
     }
 
     fn door_loop(self : &Self) -> u64 {
         let mut public = 1;
         for i in 0.. {
-            if i % 4048 == 0 {
-                println!("Loop try {}", i);
-            }
             public = (public * 7) % 20201227;
             if public == self.door_pub {
-                println!("Door loop: {}", i);
                 return i;
             }
         }
@@ -37,4 +39,21 @@ impl Handshake {
     pub fn encryption_key(self : &Self) -> u64 {
         transform(self.door_loop(), self.card_pub)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Handshake;
+
+    #[test]
+    fn encryption_key() {
+        let handshake = Handshake::from_strings("17807724", "5764801").unwrap();
+        assert_eq!(handshake.encryption_key(), 14897079);
+    }
+
+    #[test]
+    fn malformed() {
+        let err = Handshake::from_strings("17807724", "x").err().unwrap();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,165 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use super::expressions::ExpressionError;
+
+// A problem with puzzle input, positions are 1 based
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub day : Option<i8>,
+    pub line : usize,
+    pub column : usize,
+    pub message : String
+}
+
+// Anything that can go wrong loading a puzzle
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ParseError),
+    // Input that parsed but could not be evaluated
+    Eval(ExpressionError)
+}
+
+// Column of a slice taken from within string, 1 if it was taken from elsewhere
+pub fn column(string : &str, part : &str) -> usize {
+    let start = string.as_ptr() as usize;
+    let offset = (part.as_ptr() as usize).wrapping_sub(start);
+    if offset <= string.len() && string.is_char_boundary(offset) {
+        string[..offset].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+impl ParseError {
+    pub fn new(column : usize, message : &str) -> ParseError {
+        ParseError {
+            day : None,
+            line : 1,
+            column : column,
+            message : message.to_string()
+        }
+    }
+
+    // Error found in part of string, relative to the start of string
+    pub fn at(string : &str, part : &str, message : &str) -> ParseError {
+        ParseError::new(column(string, part), message)
+    }
+
+    // Parsers report positions relative to the text they were given, these
+    // move the error to its place in the surrounding input
+    pub fn offset_lines(self : Self, lines : usize) -> ParseError {
+        ParseError { line : self.line + lines, ..self }
+    }
+
+    pub fn offset_columns(self : Self, columns : usize) -> ParseError {
+        ParseError { column : self.column + columns, ..self }
+    }
+
+    // Move an error found in part to its position in the enclosing string
+    pub fn within(self : Self, string : &str, part : &str) -> ParseError {
+        self.offset_columns(column(string, part) - 1)
+    }
+
+    pub fn on_day(self : Self, day : i8) -> ParseError {
+        ParseError { day : Some(day), ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "day {}, ", day)?;
+        }
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl fmt::Display for Error {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::Eval(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(self : &Self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Parse(err) => Some(err),
+            Error::Eval(err) => Some(err)
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err : io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err : ParseError) -> Error {
+        Error::Parse(err)
+    }
+}
+
+impl From<ExpressionError> for Error {
+    fn from(err : ExpressionError) -> Error {
+        Error::Eval(err)
+    }
+}
+
+// Parse each line with parse, numbering any error by its line
+pub fn parse_lines<I, T, F>(lines : I, parse : F) -> Result<Vec<T>, ParseError>
+  where I : Iterator<Item = String>,
+        F : Fn(&str) -> Result<T, ParseError>
+{
+    lines.enumerate().map(
+        |(index, line)| parse(&line).map_err(
+            |err| err.offset_lines(index)
+        )
+    ).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::column;
+    use super::parse_lines;
+    use super::ParseError;
+
+    #[test]
+    fn columns() {
+        let line = "mem[8] = 11";
+        assert_eq!(column(line, &line[4..]), 5);
+        assert_eq!(column(line, &String::from("11")), 1);
+        let err = ParseError::new(2, "bad").within(line, &line[4..]);
+        assert_eq!(err.column, 6);
+    }
+
+    #[test]
+    fn display() {
+        let err = ParseError::at("a b", "a b".split(' ').nth(1).unwrap(), "expected a number");
+        assert_eq!(err.to_string(), "line 1, column 3: expected a number");
+        assert_eq!(
+            err.offset_lines(2).on_day(9).to_string(),
+            "day 9, line 3, column 3: expected a number"
+        );
+    }
+
+    #[test]
+    fn numbered_lines() {
+        let lines = vec!("1".to_string(), "2".to_string(), "x".to_string());
+        let err = parse_lines(lines.into_iter(), |line| line.parse::<u8>().map_err(
+            |_| ParseError::new(1, "expected a number")
+        )).err().unwrap();
+        assert_eq!(err.line, 3);
+    }
+}
@@ -3,6 +3,7 @@ use std::iter::FromIterator;
 
 
 // find pair of numbers in values list that sum together to make total
+#[allow(clippy::filter_next)]
 fn _find_2_summands(value_set : &HashSet<i64>, total : i64) -> Option<(i64, i64)> {
     value_set.iter().filter(
         |&value| value_set.contains(&(total - value))
@@ -21,6 +22,7 @@ fn _find_3_summands(value_set : &HashSet<i64>, total : i64) -> Option<(i64, i64,
 }
 
 // find pair of numbers in values list that sum together to make total
+#[allow(clippy::ptr_arg)]
 pub fn find_2_summands(values : &Vec<i64>, total : i64) -> Option<(i64, i64)> {
     // Query set to avoid quadratic check
     let value_set : HashSet<i64> = 
@@ -29,9 +31,31 @@ pub fn find_2_summands(values : &Vec<i64>, total : i64) -> Option<(i64, i64)> {
 }
 
 // find triple of numbers in values list that sum together to make total
+#[allow(clippy::ptr_arg)]
 pub fn find_3_summands(values : &Vec<i64>, total : i64) -> Option<(i64, i64, i64)> {
     // Query set to avoid quadratic check
     let value_set : HashSet<i64> = 
       HashSet::from_iter(values.iter().cloned());
     _find_3_summands(&value_set, total)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_2_summands;
+    use super::find_3_summands;
+
+    const EXAMPLE : [i64; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn two_summands() {
+        let (a, b) = find_2_summands(&EXAMPLE.to_vec(), 2020).unwrap();
+        assert_eq!(a * b, 514579);
+        assert_eq!(find_2_summands(&EXAMPLE.to_vec(), 1), None);
+    }
+
+    #[test]
+    fn three_summands() {
+        let (a, b, c) = find_3_summands(&EXAMPLE.to_vec(), 2020).unwrap();
+        assert_eq!(a * b * c, 241861950);
+    }
+}
@@ -1,5 +1,14 @@
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::iter::Peekable;
+
+use num_bigint::BigInt;
 use pest::Parser;
 use itertools::Itertools;
+use pest::error::LineColLocation;
+
+use super::error::ParseError;
 
 
 #[derive(Parser)]
@@ -9,13 +18,187 @@ struct ExpressionsParser;
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Op {
     Plus,
-    Mult
+    Minus,
+    Mult,
+    Div,
+    Pow
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Assoc {
+    Left,
+    Right
+}
+
+// How tightly each operator binds, higher levels are applied first
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Precedence {
+    levels : [(u8, Assoc); 5],
+    // Unary minus takes in every operator above this level
+    negation : u8
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EvalError {
+    DivisionByZero,
+    // Exponents have to be between 0 and u32::MAX
+    InvalidExponent(BigInt),
+    // The result does not fit in an i64, evaluate_big gives it exactly
+    Overflow
+}
+
+// An expression that could not be evaluated, and why
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExpressionError {
+    pub expression : Expression,
+    pub error : EvalError
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Expression {
     Num(i64),
-    Expression(Box<Expression>, Vec<(Op, Expression)>)
+    Neg(Box<Expression>),
+    Binary(Op, Box<Expression>, Box<Expression>)
+}
+
+impl Op {
+    fn index(self : Self) -> usize {
+        match self {
+            Op::Plus => 0,
+            Op::Minus => 1,
+            Op::Mult => 2,
+            Op::Div => 3,
+            Op::Pow => 4
+        }
+    }
+
+    pub fn apply(self : Self, left : i64, right : i64) -> Result<i64, EvalError> {
+        let result = match self {
+            Op::Plus => left.checked_add(right),
+            Op::Minus => left.checked_sub(right),
+            Op::Mult => left.checked_mul(right),
+            Op::Div => if right == 0 {
+                return Err(EvalError::DivisionByZero);
+            } else {
+                left.checked_div(right)
+            },
+            Op::Pow => {
+                let exponent = u32::try_from(right).map_err(
+                    |_| EvalError::InvalidExponent(BigInt::from(right))
+                )?;
+                left.checked_pow(exponent)
+            }
+        };
+        result.ok_or(EvalError::Overflow)
+    }
+
+    pub fn apply_big(self : Self, left : BigInt, right : BigInt) -> Result<BigInt, EvalError> {
+        match self {
+            Op::Plus => Ok(left + right),
+            Op::Minus => Ok(left - right),
+            Op::Mult => Ok(left * right),
+            Op::Div => if right == BigInt::from(0) {
+                Err(EvalError::DivisionByZero)
+            } else {
+                Ok(left / right)
+            },
+            Op::Pow => match u32::try_from(&right) {
+                Ok(exponent) => Ok(left.pow(exponent)),
+                Err(_) => Err(EvalError::InvalidExponent(right))
+            }
+        }
+    }
+}
+
+impl Precedence {
+    // Every operator on one level, applied left to right
+    pub fn left_to_right() -> Precedence {
+        Precedence { levels : [(0, Assoc::Left); 5], negation : u8::MAX }
+    }
+
+    // Addition and subtraction before multiplication and division
+    pub fn addition_first() -> Precedence {
+        Precedence::left_to_right()
+            .with(Op::Plus, 2, Assoc::Left)
+            .with(Op::Minus, 2, Assoc::Left)
+            .with(Op::Mult, 1, Assoc::Left)
+            .with(Op::Div, 1, Assoc::Left)
+            .with_negation(3)
+            .with(Op::Pow, 4, Assoc::Right)
+    }
+
+    pub fn standard() -> Precedence {
+        Precedence::left_to_right()
+            .with(Op::Plus, 1, Assoc::Left)
+            .with(Op::Minus, 1, Assoc::Left)
+            .with(Op::Mult, 2, Assoc::Left)
+            .with(Op::Div, 2, Assoc::Left)
+            .with_negation(3)
+            .with(Op::Pow, 4, Assoc::Right)
+    }
+
+    pub fn with(mut self : Self, op : Op, level : u8, assoc : Assoc) -> Precedence {
+        self.levels[op.index()] = (level, assoc);
+        self
+    }
+
+    pub fn with_negation(mut self : Self, level : u8) -> Precedence {
+        self.negation = level;
+        self
+    }
+
+    pub fn level(self : &Self, op : Op) -> (u8, Assoc) {
+        self.levels[op.index()]
+    }
+
+    pub fn negation(self : &Self) -> u8 {
+        self.negation
+    }
+}
+
+// An operand with its leading minus signs, each of which takes in the
+// operators after it that bind more tightly than negation
+fn negate<I>(
+    negations : usize,
+    term : Expression,
+    operands : &mut Peekable<I>,
+    precedence : &Precedence
+) -> Expression
+  where I : Iterator<Item = (Op, usize, Expression)>
+{
+    if negations == 0 {
+        return term;
+    }
+    let inner = negate(negations - 1, term, operands, precedence);
+    let inner = climb(inner, operands, precedence.negation() as u16 + 1, precedence);
+    Expression::Neg(Box::new(inner))
+}
+
+// Join left to the operands that follow it, taking in every operator that
+// binds at least as tightly as min_level
+fn climb<I>(
+    mut left : Expression,
+    operands : &mut Peekable<I>,
+    min_level : u16,
+    precedence : &Precedence
+) -> Expression
+  where I : Iterator<Item = (Op, usize, Expression)>
+{
+    while let Some(&(op, _, _)) = operands.peek() {
+        let (level, assoc) = precedence.level(op);
+        if (level as u16) < min_level {
+            break;
+        }
+        let (_, negations, term) = operands.next().unwrap();
+        let next_level = match assoc {
+            Assoc::Left => level as u16 + 1,
+            Assoc::Right => level as u16
+        };
+        let right = negate(negations, term, operands, precedence);
+        let right = climb(right, operands, next_level, precedence);
+        left = Expression::Binary(op, Box::new(left), Box::new(right));
+    }
+    left
 }
 
 use pest::iterators::Pair;
@@ -23,84 +206,268 @@ use pest::iterators::Pair;
 fn parse_op(pair : Pair<Rule>) -> Op {
     match pair.as_rule() {
         Rule::add => Op::Plus,
+        Rule::subtract => Op::Minus,
         Rule::multiply => Op::Mult,
+        Rule::divide => Op::Div,
+        Rule::power => Op::Pow,
         _ => unreachable!()
     }
 }
     
-fn parse_expr(pair : Pair<Rule>) -> Expression {
+fn parse_expr(pair : Pair<Rule>, precedence : &Precedence) -> Result<Expression, ParseError> {
     match pair.as_rule() {
-        Rule::num => Expression::Num(pair.as_str().parse().unwrap()),
-        Rule::term => parse_expr(pair.into_inner().next().unwrap()),
+        Rule::num => pair.as_str().parse().map(Expression::Num).map_err(
+            |_| {
+                let (_, column) = pair.as_span().start_pos().line_col();
+                ParseError::new(column, "number out of range")
+            }
+        ),
         Rule::expression => {
             let mut inner = pair.into_inner();
-            let expr = parse_expr(inner.next().unwrap());
-            let rest : Vec<(Op, Expression)> = inner.tuples::<(_, _)>().map(
-                |combine| (parse_op(combine.0), parse_expr(combine.1))
-            ).collect();
-            Expression::Expression(Box::new(expr), rest)
+            let (negations, first) = parse_operand(inner.next().unwrap(), precedence)?;
+            let rest = inner.tuples::<(_, _)>().map(
+                |(op, operand)| {
+                    let op = parse_op(op);
+                    parse_operand(operand, precedence).map(|(negations, term)| (op, negations, term))
+                }
+            ).collect::<Result<Vec<(Op, usize, Expression)>, ParseError>>()?;
+            let mut operands = rest.into_iter().peekable();
+            let first = negate(negations, first, &mut operands, precedence);
+            Ok(climb(first, &mut operands, 0, precedence))
         }
         _ => unreachable!()
     }
 }
 
+// The number of minus signs in front of a term, and the term
+fn parse_operand(pair : Pair<Rule>, precedence : &Precedence) -> Result<(usize, Expression), ParseError> {
+    let mut negations = 0;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::negation => negations += 1,
+            _ => return parse_expr(inner, precedence).map(|term| (negations, term))
+        }
+    }
+    unreachable!()
+}
+
 
 impl Expression {
 
-    pub fn from_string(string : &str) -> Option<Expression> {
-        let problem = ExpressionsParser::parse(Rule::problem, string).expect(
-            "unsuccessful parse"
-        ).next().unwrap();
-        Some(
-            parse_expr(problem.into_inner().next().unwrap())
-        )
+    // The tree grouped the way precedence says, brackets aside
+    pub fn from_string(string : &str, precedence : &Precedence) -> Result<Expression, ParseError> {
+        let problem = ExpressionsParser::parse(Rule::problem, string).map_err(
+            |err| {
+                let column = match err.line_col {
+                    LineColLocation::Pos((_, column)) => column,
+                    LineColLocation::Span((_, column), _) => column
+                };
+                ParseError::new(column, &err.variant.message())
+            }
+        )?.next().unwrap();
+        parse_expr(problem.into_inner().next().unwrap(), precedence)
     }
 
-    pub fn compute(self : &Self) -> i64 {
+    pub fn evaluate(self : &Self) -> Result<i64, EvalError> {
         match self {
-            Expression::Num(val) => *val,
-            Expression::Expression(
-                initial,
-                rest
-            ) => {
-                let mut val = initial.compute();
-                for (op, expr) in rest.iter() {
-                    match op {
-                        Op::Plus => val = val + expr.compute(),
-                        Op::Mult => val = val * expr.compute()
-                    };
-                }
-                val
-            }
+            Expression::Num(val) => Ok(*val),
+            Expression::Neg(expr) => expr.evaluate()?.checked_neg().ok_or(EvalError::Overflow),
+            Expression::Binary(op, left, right) => op.apply(left.evaluate()?, right.evaluate()?)
+        }
+    }
+
+    // Exact whatever the size of the result
+    pub fn evaluate_big(self : &Self) -> Result<BigInt, EvalError> {
+        match self {
+            Expression::Num(val) => Ok(BigInt::from(*val)),
+            Expression::Neg(expr) => Ok(-expr.evaluate_big()?),
+            Expression::Binary(op, left, right) => op.apply_big(left.evaluate_big()?, right.evaluate_big()?)
         }
     }
+}
+
+impl fmt::Display for Op {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Op::Plus => "+",
+            Op::Minus => "-",
+            Op::Mult => "*",
+            Op::Div => "/",
+            Op::Pow => "^"
+        };
+        write!(f, "{}", symbol)
+    }
+}
 
-    pub fn compute_precedent(self : &Self) -> i64 {
+// Every operation in brackets, negation included, so it reads back the same
+// under any precedence
+impl fmt::Display for Expression {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Expression::Num(val) => *val,
-            Expression::Expression(initial,rest) => { 
-                match &rest[..] {
-                    [] => initial.compute_precedent(),
-                    [(op, expr)] => match op {
-                        Op::Plus => initial.compute_precedent() + expr.compute_precedent(),
-                        Op::Mult => initial.compute_precedent() * expr.compute_precedent()
-                    },
-                    _ => {
-                        let index = rest.iter().enumerate().filter(
-                            |(_,(op,_))| *op == Op::Mult
-                        ).map(
-                            |(i,_)| i 
-                        ).next().unwrap_or(0);
-                        let op = rest[index].0;
-                        let expr1 = Expression::Expression(initial.clone(), rest[0..index].to_vec());
-                        let expr2 = Expression::Expression(Box::new(rest[index].1.clone()), rest[index+1..].to_vec());
-                        match op {
-                            Op::Plus => expr1.compute_precedent() + expr2.compute_precedent(),
-                            Op::Mult => expr1.compute_precedent() * expr2.compute_precedent()
-                        }
-                    }
+            Expression::Num(val) => write!(f, "{}", val),
+            Expression::Neg(expr) => write!(f, "(-{})", expr),
+            Expression::Binary(op, left, right) => write!(f, "({} {} {})", left, op, right)
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::InvalidExponent(exponent) => write!(f, "invalid exponent {}", exponent),
+            EvalError::Overflow => write!(f, "result does not fit in 64 bits")
+        }
+    }
+}
+
+impl error::Error for EvalError {}
+
+impl ExpressionError {
+    pub fn new(expression : &Expression, error : EvalError) -> ExpressionError {
+        ExpressionError { expression : expression.clone(), error : error }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not evaluate {}: {}", self.expression, self.error)
+    }
+}
+
+impl error::Error for ExpressionError {}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+
+    use super::Assoc;
+    use super::EvalError;
+    use super::Expression;
+    use super::Op;
+    use super::Precedence;
+
+    const EXAMPLES : [(&str, i64, i64); 6] = [
+        ("1 + 2 * 3 + 4 * 5 + 6", 71, 231),
+        ("1 + (2 * 3) + (4 * (5 + 6))", 51, 51),
+        ("2 * 3 + (4 * 5)", 26, 46),
+        ("5 + (8 * 3 + 9 + 3 * 4 * 3)", 437, 1445),
+        ("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12240, 669060),
+        ("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 13632, 23340)
+    ];
+
+    fn evaluate(string : &str, precedence : &Precedence) -> Result<i64, EvalError> {
+        Expression::from_string(string, precedence).unwrap().evaluate()
+    }
+
+    fn tree(string : &str, precedence : &Precedence) -> String {
+        Expression::from_string(string, precedence).unwrap().to_string()
+    }
+
+    #[test]
+    fn left_to_right() {
+        for (string, expected, _) in EXAMPLES.iter() {
+            assert_eq!(evaluate(string, &Precedence::left_to_right()), Ok(*expected), "{}", string);
+        }
+    }
+
+    #[test]
+    fn addition_first() {
+        for (string, _, expected) in EXAMPLES.iter() {
+            assert_eq!(evaluate(string, &Precedence::addition_first()), Ok(*expected), "{}", string);
+        }
+    }
+
+    #[test]
+    fn standard() {
+        let standard = Precedence::standard();
+        assert_eq!(evaluate("1 + 2 * 3 - 4 / 2", &standard), Ok(5));
+        assert_eq!(evaluate("2 ^ 3 ^ 2", &standard), Ok(512));
+        assert_eq!(evaluate("10 - 4 - 3", &standard), Ok(3));
+        assert_eq!(evaluate("-2 ^ 2 * -(1 + 2)", &standard), Ok(12));
+        assert_eq!(evaluate("-2 ^ 2", &standard), Ok(-4));
+        assert_eq!(evaluate("(-2) ^ 2", &standard), Ok(4));
+        assert_eq!(evaluate("2 ^ -3 ^ 0", &standard), Err(EvalError::InvalidExponent(BigInt::from(-1))));
+        assert_eq!(evaluate("-2 ^ 2", &Precedence::left_to_right()), Ok(4));
+        assert_eq!(evaluate("-2 ^ 2", &standard.clone().with_negation(5)), Ok(4));
+        assert_eq!(evaluate("7 / 2 - -7 / 2", &standard), Ok(6));
+        assert_eq!(evaluate("1 + 2 * 3", &Precedence::left_to_right()), Ok(9));
+        let custom = Precedence::left_to_right().with(Op::Minus, 0, Assoc::Right);
+        assert_eq!(evaluate("10 - 4 - 3", &custom), Ok(9));
+    }
+
+    #[test]
+    fn trees() {
+        assert_eq!(tree("1 + 2 * 3", &Precedence::standard()), "(1 + (2 * 3))");
+        assert_eq!(tree("1 + 2 * 3", &Precedence::left_to_right()), "((1 + 2) * 3)");
+        assert_eq!(tree("2 * 3 + 4", &Precedence::addition_first()), "(2 * (3 + 4))");
+        assert_eq!(tree("2 ^ 3 ^ 2 - 1 - 1", &Precedence::standard()), "(((2 ^ (3 ^ 2)) - 1) - 1)");
+        assert_eq!(tree("-(1 + 2) * --3", &Precedence::standard()), "((-(1 + 2)) * (-(-3)))");
+        assert_eq!(tree("((7))", &Precedence::standard()), "7");
+        assert_eq!(tree("-2 ^ 2 * 3", &Precedence::standard()), "((-(2 ^ 2)) * 3)");
+        assert_eq!(tree("--2 ^ 2", &Precedence::standard()), "(-(-(2 ^ 2)))");
+        assert_eq!(tree("2 * -3 ^ 2 + 1", &Precedence::standard()), "((2 * (-(3 ^ 2))) + 1)");
+        for string in ["(-2) ^ 2", "-2 ^ 2 * -(1 + 2) - -3 ^ -1 ^ 2", "(-(1 - 2)) ^ 3 / --4"].iter() {
+            for precedence in [Precedence::standard(), Precedence::left_to_right(), Precedence::addition_first()].iter() {
+                let expression = Expression::from_string(string, precedence).unwrap();
+                let printed = expression.to_string();
+                for other in [Precedence::standard(), Precedence::left_to_right(), Precedence::addition_first()].iter() {
+                    assert_eq!(Expression::from_string(&printed, other).unwrap(), expression, "{}", printed);
                 }
             }
         }
+        assert_eq!(tree("(-2) ^ 2", &Precedence::standard()), "((-2) ^ 2)");
+        let expression = Expression::from_string(EXAMPLES[5].0, &Precedence::addition_first()).unwrap();
+        let printed = expression.to_string();
+        assert_eq!(Expression::from_string(&printed, &Precedence::left_to_right()).unwrap(), expression);
+        assert_eq!(Expression::from_string(&printed, &Precedence::standard()).unwrap().evaluate(), Ok(EXAMPLES[5].2));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(evaluate("1 / (2 - 2)", &Precedence::standard()), Err(EvalError::DivisionByZero));
+        assert_eq!(evaluate("2 ^ -1", &Precedence::standard()), Err(EvalError::InvalidExponent(BigInt::from(-1))));
+        assert_eq!(EvalError::DivisionByZero.to_string(), "division by zero");
+    }
+
+    #[test]
+    fn overflow() {
+        let standard = Precedence::standard();
+        for string in [
+            "9223372036854775807 + 1",
+            "-9223372036854775807 - 2",
+            "3037000500 * 3037000500",
+            "2 ^ 63",
+            "-(-9223372036854775807 - 1)",
+            "-2 ^ 63",
+            "(-9223372036854775807 - 1) / -1"
+        ].iter() {
+            assert_eq!(evaluate(string, &standard), Err(EvalError::Overflow), "{}", string);
+        }
+        assert_eq!(evaluate("(-2) ^ 63", &standard), Ok(i64::MIN));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn big() {
+        let big = |string : &str| Expression::from_string(string, &Precedence::standard()).unwrap().evaluate_big();
+        assert_eq!(big("9223372036854775807 + 1"), Ok(BigInt::from(9223372036854775808u64)));
+        assert_eq!(big("2 ^ 64 - 1"), Ok(BigInt::from(u64::MAX)));
+        assert_eq!(big("2 ^ 100 / 2 ^ 99"), Ok(BigInt::from(2)));
+        assert_eq!(big("-7 / 2"), Ok(BigInt::from(-3)));
+        assert_eq!(big("1 / (1 - 1)"), Err(EvalError::DivisionByZero));
+        assert_eq!(big("2 ^ (2 ^ 40)"), Err(EvalError::InvalidExponent(BigInt::from(1u64 << 40))));
+        for (string, _, expected) in EXAMPLES.iter() {
+            let expression = Expression::from_string(string, &Precedence::addition_first()).unwrap();
+            assert_eq!(expression.evaluate_big(), Ok(BigInt::from(*expected)), "{}", string);
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let parse = |string : &str| Expression::from_string(string, &Precedence::standard());
+        assert!(parse("1 + (2 * 3").is_err());
+        assert!(parse("1 + x").is_err());
+        assert!(parse("1 * * 2").is_err());
+        assert!(parse("-(1) ^ 2").is_ok());
+    }
+}
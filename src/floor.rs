@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use super::error::ParseError;
+
 enum Direction {
     East,
     West,
@@ -65,15 +67,19 @@ pub struct Path {
 }
 
 impl Path {
-    pub fn from_line(string : &str) -> Path {
+    pub fn from_line(string : &str) -> Result<Path, ParseError> {
         let mut directions : Vec<Direction> = Vec::new();
         let mut chars = string.chars();
-        while let Some(dir) = Direction::from_iter(&mut chars) {
+        while !chars.as_str().is_empty() {
+            let rest = chars.as_str();
+            let dir = Direction::from_iter(&mut chars).ok_or(
+                ParseError::at(string, rest, "expected one of e, w, ne, nw, se or sw")
+            )?;
             directions.push(dir);
         }
-        Path {
+        Ok(Path {
             directions : directions
-        }
+        })
     }
 
     fn end_point(self : &Self) -> (i64, i64) {
@@ -104,6 +110,7 @@ impl Floor {
         }
     }
 
+    #[allow(clippy::ptr_arg)]
     pub fn apply_paths(self : &mut Self,paths : &Vec<Path>) {
         for path in paths.iter() {
             self.flip(path.end_point())
@@ -115,8 +122,7 @@ impl Floor {
     }
 
     pub fn simulate(self : &mut Self, days : usize) {
-        for i in 0..days {
-            println!("Day {}: {}", i, self.len());
+        for _ in 0..days {
             self.simulate_once();
         }
     }
@@ -164,4 +170,59 @@ impl Floor {
             self.black_tiles.insert(pos);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Floor;
+    use super::Path;
+
+    const EXAMPLE : &str = "sesenwnenenewseeswwswswwnenewsewsw\n\
+                            neeenesenwnwwswnenewnwwsewnenwseswesw\n\
+                            seswneswswsenwwnwse\n\
+                            nwnwneseeswswnenewneswwnewseswneseene\n\
+                            swweswneswnenwsewnwneneseenw\n\
+                            eesenwseswswnenwswnwnwsewwnwsene\n\
+                            sewnenenenesenwsewnenwwwse\n\
+                            wenwwweseeeweswwwnwwe\n\
+                            wsweesenenewnwwnwsenewsenwwsesesenwne\n\
+                            neeswseenwwswnwswswnw\n\
+                            nenwswwsewswnenenewsenwsenwnesesenew\n\
+                            enewnwewneswsewnwswenweswnenwsenwsw\n\
+                            sweneswneswneneenwnewenewwneswswnese\n\
+                            swwesenesewenwneswnwwneseswwne\n\
+                            enesenwswwswneneswsenwnewswseenwsese\n\
+                            wnwnesenesenenwwnenwsewesewsesesew\n\
+                            nenewswnwewswnenesenwnesewesw\n\
+                            eneswnwswnwsenenwnwnwwseeswneewsenese\n\
+                            neswnwewnwnwseenwseesewsenwsweewe\n\
+                            wseweeenwnesenwwwswnew";
+
+    fn floor() -> Floor {
+        let paths : Vec<Path> = EXAMPLE.lines().map(
+            |line| Path::from_line(line).unwrap()
+        ).collect();
+        let mut floor = Floor::new();
+        floor.apply_paths(&paths);
+        floor
+    }
+
+    #[test]
+    fn apply_paths() {
+        assert_eq!(floor().len(), 10);
+    }
+
+    #[test]
+    fn simulate() {
+        let mut floor = floor();
+        floor.simulate(1);
+        assert_eq!(floor.len(), 15);
+        floor.simulate(99);
+        assert_eq!(floor.len(), 2208);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Path::from_line("esenx").err().unwrap().column, 4);
+    }
+}
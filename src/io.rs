@@ -1,8 +1,9 @@
-use std::io::BufRead;
 use std::fs;
-use std::fs::File;
-use std::io::BufReader;
 use std::hash::Hash;
+use std::io;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 use super::passwords as passwords;
 use super::map as map;
@@ -26,220 +27,305 @@ use super::allergens as allergens;
 use super::crabs as crabs;
 use super::floor as floor;
 use super::door as door;
+use super::error::Error;
+use super::error::ParseError;
+use super::error::parse_lines;
 
-pub fn input_as_list(day: i8) -> Vec<i64> {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    reader.lines().map(
-        |s| s.expect("Read failure").parse::<i64>().unwrap()
-    ).collect()
-}
-pub fn input_as_comma_list(day: i8) -> Vec<u64> {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    data.split(",").map(
-        |s| s.parse::<u64>().unwrap()
-    ).collect()
+// Where puzzle input is read from
+pub enum Source<'a> {
+    // A directory laid out like data/ with a day-N.txt file per day
+    Directory(PathBuf),
+    // A single file holding the input for the requested day
+    File(PathBuf),
+    Stdin,
+    Text(&'a str)
 }
-pub fn input_as_password_database(day: i8) -> passwords::Database {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    let mut database = passwords::Database::new();
-    for line in reader.lines() {
-        database.add_line(&line.expect("Read failure"));
+
+impl<'a> Source<'a> {
+    pub fn data() -> Source<'a> {
+        Source::Directory(PathBuf::from("data"))
+    }
+
+    fn read_extension(self : &Self, day : i8, extension : &str) -> io::Result<String> {
+        match self {
+            Source::Directory(root) => fs::read_to_string(
+                root.join(format!("day-{}.{}", day, extension))
+            ),
+            Source::File(path) => fs::read_to_string(path),
+            Source::Stdin => {
+                let mut data = String::new();
+                io::stdin().read_to_string(&mut data)?;
+                Ok(data)
+            },
+            Source::Text(text) => Ok(text.to_string())
+        }
+    }
+
+    pub fn read(self : &Self, day : i8) -> io::Result<String> {
+        self.read_extension(day, "txt")
     }
-    database
-}
-pub fn input_as_map(day: i8) -> map::Map {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    let mut map = map::Map::new();
-    for line in reader.lines() {
-        map.add_line(&line.expect("Read failure"));
+
+    // Day 19 keeps its rules in a separate file when read from a directory,
+    // otherwise the rules come first separated from the messages by a blank line
+    pub fn read_rules(self : &Self, day : i8) -> io::Result<(String, String)> {
+        match self {
+            Source::Directory(_) => Ok(
+                (self.read_extension(day, "rules")?, self.read(day)?)
+            ),
+            _ => {
+                let data = self.read(day)?;
+                let (rules, messages) = data.split_once("\n\n").unwrap_or((&data, ""));
+                Ok((rules.to_string(), messages.to_string()))
+            }
+        }
     }
-    map
 }
-pub fn input_as_passports(day : i8) -> Vec<passport::Passport> {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
+
+fn owned_lines(data : &str) -> impl Iterator<Item = String> + '_ {
+    data.lines().map(
+        |line| line.to_string()
+    )
+}
+
+// Parse each blank line separated chunk, numbering errors from the start of data
+fn parse_chunks<T, F>(data : &str, parse : F) -> Result<Vec<T>, ParseError>
+  where F : Fn(&str) -> Result<T, ParseError>
+{
+    let mut chunk_start = 0;
     data.split("\n\n").map(
-        |chunk| passport::Passport::new(chunk)
+        |chunk| {
+            let parsed = parse(chunk).map_err(
+                |err| err.offset_lines(chunk_start)
+            );
+            chunk_start += chunk.lines().count() + 1;
+            parsed
+        }
+    ).collect()
+}
+
+fn parse_number<T : FromStr>(string : &str) -> Result<T, ParseError> {
+    string.parse::<T>().map_err(
+        |_| ParseError::new(1, "expected a number")
+    )
+}
+
+pub fn input_as_list(source : &Source, day : i8) -> Result<Vec<i64>, Error> {
+    let data = source.read(day)?;
+    parse_lines(owned_lines(&data), parse_number).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_comma_list(source : &Source, day : i8) -> Result<Vec<u64>, Error> {
+    let data = source.read(day)?;
+    let line = data.trim_end();
+    line.split(",").map(
+        |s| parse_number(s).map_err(
+            |err| err.within(line, s).on_day(day).into()
+        )
+    ).collect()
+}
+pub fn input_as_digits(source : &Source, day : i8) -> Result<Vec<usize>, Error> {
+    let data = source.read(day)?;
+    let line = data.trim_end();
+    line.chars().enumerate().map(
+        |(index, ch)| ch.to_digit(10).map(
+            |digit| digit as usize
+        ).ok_or(
+            ParseError::new(index + 1, "expected a digit").on_day(day).into()
+        )
     ).collect()
 }
-pub fn input_as_plane(day : i8) -> ticket::Plane {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    let seats : Vec<ticket::Seat> = reader.lines().map(
-        |line| ticket::Seat::from_string(&line.expect("ReadFailure")).unwrap()
-    ).collect();
+pub fn input_as_password_database(source : &Source, day : i8) -> Result<passwords::Database, Error> {
+    let data = source.read(day)?;
+    passwords::Database::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_map(source : &Source, day : i8) -> Result<map::Map, Error> {
+    let data = source.read(day)?;
+    map::Map::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_passports(source : &Source, day : i8) -> Result<Vec<passport::Passport>, Error> {
+    let data = source.read(day)?;
+    parse_chunks(&data, passport::Passport::from_string).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_plane(source : &Source, day : i8) -> Result<ticket::Plane, Error> {
+    let data = source.read(day)?;
+    let seats : Vec<ticket::Seat> = parse_lines(owned_lines(&data), ticket::Seat::from_string).map_err(
+        |err| err.on_day(day)
+    )?;
     let mut plane = ticket::Plane::new();
     for seat in seats.iter() {
         plane.add_seat(seat);
     }
-    plane
+    Ok(plane)
 }
-pub fn input_as_forms(day : i8) -> Vec<customs::Form> {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    data.split("\n\n").map(
-        |chunk| customs::Form::new(chunk)
-    ).collect()
+pub fn input_as_forms(source : &Source, day : i8) -> Result<Vec<customs::Form>, Error> {
+    let data = source.read(day)?;
+    parse_chunks(&data, customs::Form::from_string).map_err(
+        |err| err.on_day(day).into()
+    )
 }
-pub fn input_as_rules(day : i8) -> baggage::Rules {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    let mut rules = baggage::Rules::new();
-    for line in reader.lines() {
-        rules.add_line(&line.expect("Read failure"));
-    }
-    rules
-}
-pub fn input_as_program(day : i8) -> cpu::Program {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    cpu::Program::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
-    )
-}
-pub fn input_as_ciphertext(day : i8) -> cipher::CipherText {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    cipher::CipherText::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
-    )
-}
-pub fn input_as_adaptors(day : i8) -> adaptors::Adaptors {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    adaptors::Adaptors::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
-    )
-}
-pub fn input_as_seating(day : i8) -> seating::Seating {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    seating::Seating::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
-    )
-}
-pub fn input_as_actions(day : i8) -> Vec<directions::Action> {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    reader.lines().filter_map(
-        |line| directions::Action::from_string(&line.expect("Read failure"))
-    ).collect()
+pub fn input_as_rules(source : &Source, day : i8) -> Result<baggage::Rules, Error> {
+    let data = source.read(day)?;
+    baggage::Rules::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
 }
-pub fn input_as_timetable(day : i8) -> buses::Timetable {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    let lines : Vec<String> = reader.lines().map(
-        |line| line.expect("Read failure")
-    ).collect();
-    buses::Timetable::from_lines(
-        &lines[0],
-        &lines[1]
+pub fn input_as_program(source : &Source, day : i8) -> Result<cpu::Program, Error> {
+    let data = source.read(day)?;
+    cpu::Program::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_ciphertext(source : &Source, day : i8) -> Result<cipher::CipherText, Error> {
+    let data = source.read(day)?;
+    cipher::CipherText::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_adaptors(source : &Source, day : i8) -> Result<adaptors::Adaptors, Error> {
+    let data = source.read(day)?;
+    adaptors::Adaptors::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_seating(source : &Source, day : i8) -> Result<seating::Seating, Error> {
+    let data = source.read(day)?;
+    seating::Seating::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_actions(source : &Source, day : i8) -> Result<Vec<directions::Action>, Error> {
+    let data = source.read(day)?;
+    parse_lines(owned_lines(&data), directions::Action::from_string).map_err(
+        |err| err.on_day(day).into()
     )
 }
-pub fn input_as_docking_program(day : i8) -> docking::Program {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    docking::Program::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
+pub fn input_as_timetable(source : &Source, day : i8) -> Result<buses::Timetable, Error> {
+    let data = source.read(day)?;
+    let mut lines = data.lines();
+    buses::Timetable::from_lines(
+        lines.next().unwrap_or(""),
+        lines.next().unwrap_or("")
+    ).map_err(
+        |err| err.on_day(day).into()
     )
 }
-pub fn input_as_scanning_results(day : i8) -> ticket_scanning::ScanningResult {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    let chunks : Vec<&str> = data.split("\n\n").collect();
-    ticket_scanning::ScanningResult::from_lines(
-        chunks[0].lines(),
-        chunks[1].lines().nth(1).unwrap(),
-        chunks[2].lines().skip(1)
+pub fn input_as_docking_program(source : &Source, day : i8) -> Result<docking::Program, Error> {
+    let data = source.read(day)?;
+    let program = docking::Program::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day)
+    )?;
+    docking::Computer::new().check(&program).map_err(
+        |err| ParseError::from(err).on_day(day)
+    )?;
+    Ok(program)
+}
+pub fn input_as_scanning_results(source : &Source, day : i8) -> Result<ticket_scanning::ScanningResult, Error> {
+    let data = source.read(day)?;
+    ticket_scanning::ScanningResult::from_string(&data).map_err(
+        |err| err.on_day(day).into()
     )
 }
-pub fn input_as_conway<P>(day : i8) -> conway::Conway<P>
+pub fn input_as_conway<P>(source : &Source, day : i8) -> Result<conway::Conway<P>, Error>
   where P : conway::Position + Eq + Hash + Copy
 {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    conway::Conway::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
-    )
-}
-pub fn input_as_expressions(day : i8) -> Vec<expressions::Expression> {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    reader.lines().map(|
-        line| expressions::Expression::from_string(&line.expect("Read failure")).unwrap()
+    let data = source.read(day)?;
+    conway::Conway::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+// The expressions parsed once for each precedence
+pub fn input_as_expressions(
+    source : &Source,
+    day : i8,
+    precedences : &[expressions::Precedence]
+) -> Result<Vec<Vec<expressions::Expression>>, Error> {
+    let data = source.read(day)?;
+    precedences.iter().map(
+        |precedence| parse_lines(
+            owned_lines(&data),
+            |line| expressions::Expression::from_string(line, precedence)
+        ).map_err(
+            |err| err.on_day(day).into()
+        )
     ).collect()
 }
-pub fn input_as_matching(day : i8) -> (matching::RuleSet, Vec<String>) {
-    let lines_filename = format!("data/day-{}.txt", day);
-    let lines_file = File::open(lines_filename).expect("Issue opening file");
-    let lines_reader = BufReader::new(&lines_file);
-    let lines : Vec<String> = lines_reader.lines().map(
-        |line| line.expect("Read failure")
-    ).collect();
-    let rules_filename = format!("data/day-{}.rules", day);
-    let rules_file = File::open(rules_filename).expect("Issue opening file");
-    let rules_reader = BufReader::new(&rules_file);
-    let ruleset = matching::RuleSet::from_lines(
-        rules_reader.lines().map(|line| line.expect("Read failure"))
-    );
-    (ruleset, lines)
-}
-pub fn input_as_tileset(day : i8) -> tileset::TileSet {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    tileset::TileSet::from_string(&data)
-}
-pub fn input_as_menu(day : i8) -> allergens::Menu
+pub fn input_as_matching(source : &Source, day : i8) -> Result<(matching::RuleSet, Vec<String>), Error> {
+    let (rules, messages) = source.read_rules(day)?;
+    let ruleset = matching::RuleSet::from_lines(owned_lines(&rules)).map_err(
+        |err| err.on_day(day)
+    )?;
+    Ok((ruleset, owned_lines(&messages).collect()))
+}
+pub fn input_as_tileset(source : &Source, day : i8) -> Result<tileset::TileSet, Error> {
+    let data = source.read(day)?;
+    tileset::TileSet::from_string(&data).map_err(
+        |err| err.on_day(day).into()
+    )
+}
+pub fn input_as_menu(source : &Source, day : i8) -> Result<allergens::Menu, Error>
 {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    allergens::Menu::from_lines(
-        reader.lines().map(|line| line.expect("Read failure"))
+    let data = source.read(day)?;
+    allergens::Menu::from_lines(owned_lines(&data)).map_err(
+        |err| err.on_day(day).into()
     )
 }
-pub fn input_as_game(day : i8) -> crabs::Game
+pub fn input_as_game(source : &Source, day : i8) -> Result<crabs::Game, Error>
 {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    let players : Vec<&str> = data.split("\n\n").collect();
-    crabs::Game::from_lines(
-        players[0].lines().rev(),
-        players[1].lines().rev()
+    let data = source.read(day)?;
+    crabs::Game::from_string(&data).map_err(
+        |err| err.on_day(day).into()
     )
 }
-pub fn input_as_paths(day : i8) -> Vec<floor::Path>
+pub fn input_as_paths(source : &Source, day : i8) -> Result<Vec<floor::Path>, Error>
 {
-    let filename = format!("data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    reader.lines().map(|line| 
-        floor::Path::from_line(&line.expect("Read failure"))
-    ).collect()
+    let data = source.read(day)?;
+    parse_lines(owned_lines(&data), floor::Path::from_line).map_err(
+        |err| err.on_day(day).into()
+    )
 }
-pub fn input_as_handshake(day : i8) -> door::Handshake
+pub fn input_as_handshake(source : &Source, day : i8) -> Result<door::Handshake, Error>
 {
-    let filename = format!("data/day-{}.txt", day);
-    let data = fs::read_to_string(filename).expect("Issue reading file");
-    let pub_keys : Vec<&str> = data.lines().collect();
-    door::Handshake::from_strings(pub_keys[0], pub_keys[1])
+    let data = source.read(day)?;
+    let mut pub_keys = data.lines();
+    door::Handshake::from_strings(
+        pub_keys.next().unwrap_or(""),
+        pub_keys.next().unwrap_or("")
+    ).map_err(
+        |err| err.on_day(day).into()
+    )
+}
 
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::input_as_list;
+    use super::input_as_passports;
+    use super::Source;
+
+    #[test]
+    fn read_rules_from_text() {
+        let source = Source::Text("0: \"a\"\n\na\nb");
+        let (rules, messages) = source.read_rules(19).unwrap();
+        assert_eq!(rules, "0: \"a\"");
+        assert_eq!(messages, "a\nb");
+    }
+
+    #[test]
+    fn errors_name_the_day() {
+        let err = input_as_list(&Source::Text("1\n2\nthree"), 1).err().unwrap();
+        assert_eq!(err.to_string(), "day 1, line 3, column 1: expected a number");
+    }
+
+    #[test]
+    fn chunk_lines() {
+        // Lines are counted from the start of the input, not the chunk
+        let err = input_as_passports(&Source::Text("byr:1937\n\niyr:2017\nhgt"), 4).err().unwrap();
+        assert_eq!(err.to_string(), "day 4, line 4, column 1: expected '<field>:<value>'");
+    }
+}
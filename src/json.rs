@@ -0,0 +1,52 @@
+// Just enough JSON writing for the machine readable reports
+
+pub fn string(value : &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// Fields are (name, already encoded value)
+pub fn object(fields : &[(&str, String)]) -> String {
+    let encoded : Vec<String> = fields.iter().map(
+        |(name, value)| format!("{}:{}", string(name), value)
+    ).collect();
+    format!("{{{}}}", encoded.join(","))
+}
+
+pub fn array(items : &[String]) -> String {
+    format!("[{}]", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::array;
+    use super::object;
+    use super::string;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn nesting() {
+        let inner = object(&[("x", "1".to_string())]);
+        assert_eq!(
+            array(&[object(&[("a", string("b")), ("c", inner)]), "2".to_string()]),
+            "[{\"a\":\"b\",\"c\":{\"x\":1}},2]"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+// The house style spells out `self : &Self` and `field : field`
+#![allow(clippy::needless_arbitrary_self_type, clippy::redundant_field_names, clippy::new_without_default)]
+
+pub mod memory_game;
+pub mod expenses;
+pub mod passwords;
+pub mod map;
+pub mod passport;
+pub mod ticket;
+pub mod customs;
+pub mod baggage;
+pub mod cpu;
+pub mod cipher;
+pub mod adaptors;
+pub mod seating;
+pub mod directions;
+pub mod buses;
+pub mod docking;
+pub mod ticket_scanning;
+pub mod conway;
+pub mod expressions;
+pub mod matching;
+pub mod tileset;
+pub mod allergens;
+pub mod crabs;
+pub mod cups;
+pub mod floor;
+pub mod door;
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod error;
+pub mod io;
+pub mod solution;
+pub mod challenge;
+pub mod verify;
+pub mod json;
+pub mod bench;
+
+pub use error::Error;
+pub use error::ParseError;
+pub use io::Source;
+pub use solution::Answer;
+pub use solution::Solution;
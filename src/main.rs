@@ -1,413 +1,257 @@
-#![feature(str_split_once)]
-#![feature(iter_map_while)]
-#![feature(unsigned_abs)]
+#![allow(clippy::needless_arbitrary_self_type, clippy::redundant_field_names)]
 
-mod memory_game;
-mod expenses;
-mod passwords;
-mod map;
-mod passport;
-mod ticket;
-mod customs;
-mod baggage;
-mod cpu;
-mod cipher;
-mod adaptors;
-mod seating;
-mod directions;
-mod buses;
-mod docking;
-mod ticket_scanning;
-mod conway;
-mod expressions;
-mod matching;
-mod tileset;
-mod allergens;
-mod crabs;
-mod cups;
-mod floor;
-mod door;
+use std::env;
+use std::io as std_io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
+use advent_of_code::bench;
+use advent_of_code::challenge;
+use advent_of_code::cpu;
+use advent_of_code::io;
+use advent_of_code::json;
+use advent_of_code::solution;
+use advent_of_code::solution::Entry;
+use advent_of_code::verify;
+use advent_of_code::Answer;
+use advent_of_code::Error;
 
-mod io;
+enum Command {
+    Single(i8, Option<u8>),
+    All,
+    Verify(PathBuf),
+    Bench(Option<i8>),
+    Debug
+}
 
-mod challenge {
-    use super::io as io;
-    use super::expenses as expenses;
-    use super::passwords as passwords;
-    use super::seating as seating;
-    use super::directions as directions;
-    use super::docking as docking;
-    use super::memory_game as memory_game;
-    use super::conway as conway;
-    use super::cups as cups;
-    use super::floor as floor;
+struct Options<'a> {
+    command : Command,
+    source : io::Source<'a>,
+    repeat : usize,
+    json : bool
+}
 
-    fn challenge_1() {
-        let data = io::input_as_list(1);
-        let (a, b) = expenses::find_2_summands(&data, 2020).unwrap();
-        println!("{} {} {}", a , b, a * b);
+fn parse_command(args : &[&str]) -> Option<Command> {
+    match args {
+        ["--all"] => Some(Command::All),
+        ["--verify"] => Some(Command::Verify(PathBuf::from("data/answers.txt"))),
+        ["--verify", path] => Some(Command::Verify(PathBuf::from(path))),
+        ["--bench"] => Some(Command::Bench(None)),
+        ["--debug"] => Some(Command::Debug),
+        ["--bench", day] => day.parse::<i8>().ok().map(
+            |day| Command::Bench(Some(day))
+        ),
+        [day] => day.parse::<i8>().ok().map(
+            |day| Command::Single(day, None)
+        ),
+        [day, part] => day.parse::<i8>().ok().and_then(
+            |day| part.parse::<u8>().ok().map(
+                |part| Command::Single(day, Some(part))
+            )
+        ),
+        _ => None
     }
+}
 
-    fn challenge_2() {
-        let data = io::input_as_list(1);
-        let (a, b, c) = expenses::find_3_summands(&data, 2020).unwrap();
-        println!("{} {} {} {}", a, b, c, a * b * c);
+fn parse_args<'a>(args : &[String]) -> Option<Options<'a>> {
+    let mut source = io::Source::data();
+    let mut repeat = 1;
+    let mut json = false;
+    let mut positional : Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--data" => source = io::Source::Directory(PathBuf::from(iter.next()?)),
+            "--input" => source = match iter.next()?.as_str() {
+                "-" => io::Source::Stdin,
+                path => io::Source::File(PathBuf::from(path))
+            },
+            "--repeat" => repeat = iter.next()?.parse::<usize>().ok().filter(|&n| n > 0)?,
+            "--json" => json = true,
+            _ => positional.push(arg)
+        }
     }
+    parse_command(&positional).map(
+        |command| Options { command : command, source : source, repeat : repeat, json : json }
+    )
+}
 
-    fn challenge_3() {
-        let data = io::input_as_password_database(2);
-        let num = data.count_valid(passwords::RuleSet::Sled);
-        println!("{}", num);
-    }
-    fn challenge_4() {
-        let data = io::input_as_password_database(2);
-        let num = data.count_valid(passwords::RuleSet::Toboggan);
-        println!("{}", num);
-    }
-    fn challenge_5() {
-        let data = io::input_as_map(3);
-        let num = data.count_trees_path(1, 3);
-        println!("{}", num);
-    }
-    fn challenge_6() {
-        let data = io::input_as_map(3);
-        let a = data.count_trees_path(1, 1);
-        let b = data.count_trees_path(1, 3);
-        let c = data.count_trees_path(1, 5);
-        let d = data.count_trees_path(1, 7);
-        let e = data.count_trees_path(2, 1);
-        println!("{} {} {} {} {} {}", a, b, c, d, e, a*b*c*d*e);
-    }
-    fn challenge_7() {
-        let data = io::input_as_passports(4);
-        let num = data.iter().filter(
-            |passport| passport.required_fields_exist()
-        ).count();
-        println!("{}", num);
-    }
-    fn challenge_8() {
-        let data = io::input_as_passports(4);
-        let num = data.iter().filter(
-            |passport| passport.required_fields_valid()
-        ).count();
-        println!("{}", num);
-    }
-    fn challenge_9() {
-        let data = io::input_as_plane(5);
-        let num = data.max();
-        println!("{}", num);
-    }
-    fn challenge_10() {
-        let data = io::input_as_plane(5);
-        let num = data.find_missing()[0];
-        println!("{}", num);
-    }
-    fn challenge_11() {
-        let data = io::input_as_forms(6);
-        let num : usize = data.iter().map(
-            |form| form.num_any()
-        ).sum();
-        println!("{}", num);
-    }
-    fn challenge_12() {
-        let data = io::input_as_forms(6);
-        let num : usize = data.iter().map(
-            |form| form.num_all()
-        ).sum();
-        println!("{}", num);
-    }
-    fn challenge_13() {
-        let data = io::input_as_rules(7);
-        let num = data.num_dependencies("shiny gold").unwrap();
-        println!("{}", num);
-    }
-    fn challenge_14() {
-        let data = io::input_as_rules(7);
-        let num = data.full_num_contained("shiny gold").unwrap();
-        println!("{}", num);
-    }
-    fn challenge_15() {
-        let data = io::input_as_program(8);
-        let num = data.run();
-        println!("{}", num);
-    }
-    fn challenge_16() {
-        let data = io::input_as_program(8);
-        let num = data.fix().unwrap();
-        println!("{}", num);
-    }
-    fn challenge_17() {
-        let data = io::input_as_ciphertext(9);
-        let num = data.first_prop(25).unwrap();
-        println!("{}", num);
-    }
-    fn challenge_18() {
-        let data = io::input_as_ciphertext(9);
-        let num = data.weakness(25).unwrap();
-        println!("{}", num);
-    }
-    fn challenge_19() {
-        let data = io::input_as_adaptors(10);
-        let num = data.joltage_differences();
-        println!("{}", num);
-    }
-    fn challenge_20() {
-        let data = io::input_as_adaptors(10);
-        let num = data.number_arrangements();
-        println!("{}", num);
-    }
-    fn challenge_21() {
-        let mut data = io::input_as_seating(11);
-        data.simulate(&seating::SeatingRules::Adjacent);
-        let num = data.number_occupied();
-        println!("{}", num);
-    }
-    fn challenge_22() {
-        let mut data = io::input_as_seating(11);
-        data.simulate(&seating::SeatingRules::Visible);
-        let num = data.number_occupied();
-        println!("{}", num);
-    }
-    fn challenge_23() {
-        let data = io::input_as_actions(12);
-        let mut ship = directions::Ship::new();
-        ship.simulate(data.into_iter());
-        let num = ship.distance();
-        println!("{}", num);
-    }
-    fn challenge_24() {
-        let data = io::input_as_actions(12);
-        let mut ship = directions::Ship::new();
-        ship.simulate_waypoint(data.into_iter());
-        let num = ship.distance();
-        println!("{}", num);
-    }
-    fn challenge_25() {
-        let data = io::input_as_timetable(13);
-        let (offset, bus) = data.first_bus().unwrap();
-        let num = offset * bus.id().unwrap();
-        println!("{}", num);
-    }
-    fn challenge_26() {
-        let data = io::input_as_timetable(13);
-        let num = data.first_congunction();
-        println!("{}", num);
-    }
-    fn challenge_27() {
-        let data = io::input_as_docking_program(14);
-        let mut cpu = docking::Computer::new();
-        cpu.run(&data);
-        let num = cpu.sum_variables();
-        println!("{}", num);
-    }
-    fn challenge_28() {
-        let data = io::input_as_docking_program(14);
-        let mut cpu = docking::Computer::new();
-        cpu.run_decode(&data);
-        let num = cpu.sum_variables();
-        println!("{}", num);
-    }
-    fn challenge_29() {
-        let mut data = memory_game::Game::new(
-            io::input_as_comma_list(15)
-        );
-        let num = data.nth(2020-1).unwrap();
-        println!("{}", num);
-    }
-    fn challenge_30() {
-        let mut data = memory_game::Game::new(
-            io::input_as_comma_list(15)
-        );
-        let num = data.nth(30000000-1).unwrap();
-        println!("{}", num);
-    }
-    fn challenge_31() {
-        let data = io::input_as_scanning_results(16);
-        let num = data.scanning_error_rate();
-        println!("{}", num);
-    }
-    fn challenge_32() {
-        let mut data = io::input_as_scanning_results(16);
-        data.discard_invalid();
-        let num : u64 = data.departures().iter().map(
-            |name| data.own_field(name).unwrap()
-        ).product();
-        println!("{}", num);
-    }
-    fn challenge_33() {
-        let mut data = io::input_as_conway::<conway::Position3D>(17);
-        data.simulate_n(6);
-        let num = data.num_cubes();
-        println!("{}", num);
-    }
-    fn challenge_34() {
-        let mut data = io::input_as_conway::<conway::Position4D>(17);
-        data.simulate_n(6);
-        let num = data.num_cubes();
-        println!("{}", num);
-    }
-    fn challenge_35() {
-        let data = io::input_as_expressions(18);
-        let num : i64 = data.iter().map(
-            |expr| expr.compute()
-        ).sum();
-        println!("{}", num);
-    }
-    fn challenge_36() {
-        let data = io::input_as_expressions(18);
-        let num : i64 = data.iter().map(
-            |expr| expr.compute_precedent()
-        ).sum();
-        println!("{}", num);
-    }
-    fn challenge_37() {
-        let (rules, data) = io::input_as_matching(19);
-        let num : usize = data.iter().filter(
-            |line| rules.is_match(&line)
-        ).count();
-        println!("{}", num);
-    }
-    fn challenge_38() {
-        let (mut rules, data) = io::input_as_matching(19);
-        rules.add_new_rules();
-        let num : usize = data.iter().filter(
-            |line| rules.is_match(&line)
-        ).count();
-        println!("{}", num);
-    }
-    fn challenge_39() {
-        let tileset = io::input_as_tileset(20);
-        let solution = tileset.solve(12);
-        let num : u64 = solution.corner_tiles().iter().product();
-        println!("{}", num);
-    }
-    fn challenge_40() {
-        let tileset = io::input_as_tileset(20);
-        let solution = tileset.solve(12);
-        let picture = solution.picture();
-        let total = picture.total();
-        let monster_num = picture.search_monster().len();
-        let num = total - monster_num;
-        println!("{}", num);
-    }
-    fn challenge_41() {
-        let menu = io::input_as_menu(21);
-        let num = menu.count_ingredients_no_allergens();
-        println!("{}", num);
-    }
-    fn challenge_42() {
-        let menu = io::input_as_menu(21);
-        let ingredients = menu.ordered_ingredients_allergens();
-        println!("{}", ingredients.join(","));
-    }
-    fn challenge_43() {
-        let mut data = io::input_as_game(22);
-        data.play();
-        println!("{}",data.score());
-    }
-    fn challenge_44() {
-        let mut data = io::input_as_game(22);
-        data.play_recursive();
-        println!("{}",data.score());
-    }
-    fn challenge_45() {
-        let mut data = cups::Cups::from_cycle_ints(
-            vec!(3,6,2,9,8,1,7,5,4)
-        );
-        data.simulate(100);
-        println!("{}",data.labels(1));
-    }
-    fn challenge_46() {
-        let init = vec!(3,6,2,9,8,1,7,5,4);
-        let vec :Vec<usize> = init.into_iter().chain(10..=1000000).collect();
-        let mut data = cups::Cups::from_cycle_ints(vec);
-        data.simulate(10000000);
-        let num = data.offset_labels(1, 1) * data.offset_labels(1, 2);
-        println!("{}",num);
-    }
-    fn challenge_47() {
-        let data = io::input_as_paths(24);
-        let mut floor = floor::Floor::new();
-        floor.apply_paths(&data);
-        let num = floor.len();
-        println!("{}",num);
-    }
-    fn challenge_48() {
-        let data = io::input_as_paths(24);
-        let mut floor = floor::Floor::new();
-        floor.apply_paths(&data);
-        floor.simulate(100);
-        let num = floor.len();
-        println!("{}",num);
+// One --input file or stdin only holds a single day's puzzle
+fn single_input_for_every_day(options : &Options) -> bool {
+    let every_day = matches!(options.command, Command::All | Command::Verify(_) | Command::Bench(None));
+    every_day && !matches!(options.source, io::Source::Directory(_))
+}
+
+#[derive(Clone, Copy)]
+enum Output {
+    Plain,
+    Labelled,
+    Json
+}
+
+fn answer_json(day : i8, part : u8, answer : &Answer, parse_time : Duration, solve_time : Duration) -> String {
+    json::object(&[
+        ("day", day.to_string()),
+        ("part", part.to_string()),
+        ("answer", json::string(answer.value())),
+        ("details", answer.details_json()),
+        ("parse_ms", format!("{:.3}", bench::millis(parse_time))),
+        ("solve_ms", format!("{:.3}", bench::millis(solve_time)))
+    ])
+}
+
+// Prints each part that exists, returning how many were printed
+fn print_parts(entry : &Entry, parts : &[u8], source : &io::Source, output : Output) -> Result<usize, Error> {
+    let (solution, parse_time) = bench::timed(|| entry.parse(source));
+    let solution = solution?;
+    let mut printed = 0;
+    for &part in parts {
+        let (answer, solve_time) = bench::timed(|| solution.solve(part));
+        if let Some(answer) = answer? {
+            match output {
+                Output::Plain => println!("{}", answer),
+                Output::Labelled => println!("Day {} part {}: {}", entry.day, part, answer),
+                Output::Json => println!("{}", answer_json(entry.day, part, &answer, parse_time, solve_time))
+            }
+            printed += 1;
+        }
     }
-    fn challenge_49() {
-        let data = io::input_as_handshake(25);
-        let num = data.encryption_key();
-        println!("{}",num);
+    Ok(printed)
+}
+
+fn run_day(day : i8, part : Option<u8>, output : Output, source : &io::Source) -> Result<bool, Error> {
+    let registry = challenge::registry();
+    let entry = match solution::find(&registry, day) {
+        Some(entry) => entry,
+        None => return Ok(false)
+    };
+    let printed = match part {
+        Some(part) => print_parts(entry, &[part], source, output)?,
+        None => print_parts(entry, &[1, 2], source, output)?
+    };
+    Ok(printed > 0)
+}
+
+fn run_all(output : Output, source : &io::Source) -> Result<(), Error> {
+    for entry in challenge::registry() {
+        print_parts(&entry, &[1, 2], source, output)?;
     }
+    Ok(())
+}
 
-    pub fn challenge(num : u8) {
-        match num {
-            1 => challenge_1(),
-            2 => challenge_2(),
-            3 => challenge_3(),
-            4 => challenge_4(),
-            5 => challenge_5(),
-            6 => challenge_6(),
-            7 => challenge_7(),
-            8 => challenge_8(),
-            9 => challenge_9(),
-            10 => challenge_10(),
-            11 => challenge_11(),
-            12 => challenge_12(),
-            13 => challenge_13(),
-            14 => challenge_14(),
-            15 => challenge_15(),
-            16 => challenge_16(),
-            17 => challenge_17(),
-            18 => challenge_18(),
-            19 => challenge_19(),
-            20 => challenge_20(),
-            21 => challenge_21(),
-            22 => challenge_22(),
-            23 => challenge_23(),
-            24 => challenge_24(),
-            25 => challenge_25(),
-            26 => challenge_26(),
-            27 => challenge_27(),
-            28 => challenge_28(),
-            29 => challenge_29(),
-            30 => challenge_30(),
-            31 => challenge_31(),
-            32 => challenge_32(),
-            33 => challenge_33(),
-            34 => challenge_34(),
-            35 => challenge_35(),
-            36 => challenge_36(),
-            37 => challenge_37(),
-            38 => challenge_38(),
-            39 => challenge_39(),
-            40 => challenge_40(),
-            41 => challenge_41(),
-            42 => challenge_42(),
-            43 => challenge_43(),
-            44 => challenge_44(),
-            45 => challenge_45(),
-            46 => challenge_46(),
-            47 => challenge_47(),
-            48 => challenge_48(),
-            49 => challenge_49(),
-            _ => () 
-        }
+fn print_check(check : &verify::Check) {
+    match &check.outcome {
+        verify::Outcome::Pass => println!("PASS    day {} part {}", check.day, check.part),
+        verify::Outcome::Fail { expected, actual } => println!(
+            "FAIL    day {} part {}: expected {}, got {}", check.day, check.part, expected, actual
+        ),
+        verify::Outcome::Missing { actual } => println!(
+            "MISSING day {} part {}: got {}", check.day, check.part, actual
+        ),
+        verify::Outcome::Unanswered { expected } => println!(
+            "FAIL    day {} part {}: expected {}, got no answer", check.day, check.part, expected
+        ),
+        verify::Outcome::Error(err) => println!("FAIL    day {} part {}: {}", check.day, check.part, err)
     }
 }
 
+fn run_verify(path : &Path, source : &io::Source) -> Result<(), Error> {
+    let expected = verify::Expected::load(path)?;
+    let checks = verify::verify(&challenge::registry(), &expected, source);
+    for check in checks.iter() {
+        print_check(check);
+    }
+    let summary = verify::summarize(&checks);
+    println!(
+        "{} passed, {} failed, {} missing",
+        summary.passed, summary.failed, summary.missing
+    );
+    // Missing answers are only reported, see verify::Outcome::Missing
+    if summary.failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_bench(day : Option<i8>, repeat : usize, json : bool, source : &io::Source) -> Result<bool, Error> {
+    let registry = challenge::registry();
+    let entries : Vec<&Entry> = match day {
+        Some(day) => match solution::find(&registry, day) {
+            Some(entry) => vec!(entry),
+            None => return Ok(false)
+        },
+        None => registry.iter().collect()
+    };
+    let mut timings = Vec::new();
+    for entry in entries {
+        timings.push(bench::time_entry(entry, source, repeat)?);
+    }
+    if json {
+        println!("{}", bench::to_json(&timings));
+    } else {
+        println!("{}", bench::table(&timings));
+    }
+    Ok(true)
+}
 
+// Step through the day 8 program, commands come from stdin
+fn run_debug(source : &io::Source) -> Result<(), Error> {
+    if let io::Source::Stdin = source {
+        eprintln!("The debugger reads commands from stdin, give the program with --input <file>");
+        process::exit(2);
+    }
+    let program = io::input_as_program(source, 8)?;
+    let stdin = std_io::stdin();
+    let stdout = std_io::stdout();
+    cpu::repl::repl(&program, stdin.lock(), &mut stdout.lock())?;
+    Ok(())
+}
 
 fn main() {
-    challenge::challenge(49);
-}
\ No newline at end of file
+    let args : Vec<String> = env::args().skip(1).collect();
+    let options = parse_args(&args);
+    if options.as_ref().is_some_and(single_input_for_every_day) {
+        eprintln!("--input holds a single day, use --data <dir> to run every day");
+        process::exit(2);
+    }
+    let result = match options {
+        Some(Options { command : Command::Debug, source, .. }) => run_debug(&source),
+        Some(Options { command : Command::All, source, json, .. }) => {
+            run_all(if json { Output::Json } else { Output::Labelled }, &source)
+        },
+        Some(Options { command : Command::Verify(path), source, .. }) => run_verify(&path, &source),
+        Some(Options { command : Command::Bench(day), source, repeat, json }) => {
+            run_bench(day, repeat, json, &source).map(
+                |found| if !found {
+                    eprintln!("No challenge for day {}", day.unwrap_or(0));
+                    process::exit(1);
+                }
+            )
+        },
+        Some(Options { command : Command::Single(day, part), source, json, .. }) => {
+            run_day(day, part, if json { Output::Json } else { Output::Plain }, &source).map(
+                |found| if !found {
+                    match part {
+                        Some(part) => eprintln!("No challenge for day {} part {}", day, part),
+                        None => eprintln!("No challenge for day {}", day)
+                    }
+                    process::exit(1);
+                }
+            )
+        },
+        None => {
+            eprintln!("Usage: aoc [--data <dir> | --input <file|->] [--json] <day> [part]");
+            eprintln!("       aoc [--data <dir>] [--json] --all");
+            eprintln!("       aoc [--data <dir>] --verify [answers]");
+            eprintln!("       aoc [--data <dir>] [--repeat <n>] [--json] --bench [day]");
+            eprintln!("       aoc [--data <dir> | --input <file>] --debug");
+            process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
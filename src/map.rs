@@ -1,5 +1,7 @@
 use std::cmp;
 use std::collections::HashSet;
+
+use super::error::ParseError;
 pub struct Map {
     length : usize,
     period : usize,
@@ -20,13 +22,52 @@ impl Map {
             |&i| self.lookup_tree(i_step * i, j_step * i)
         ).count()
     }
-    pub fn add_line(self : &mut Self, line : &str) {
+    pub fn from_lines<I>(lines : I) -> Result<Map, ParseError>
+      where I : Iterator<Item = String>
+    {
+        let mut map = Map::new();
+        for (index, line) in lines.enumerate() {
+            map.add_line(&line).map_err(
+                |err| err.offset_lines(index)
+            )?;
+        }
+        Ok(map)
+    }
+    pub fn add_line(self : &mut Self, line : &str) -> Result<(), ParseError> {
         for (index, ch) in line.chars().enumerate() {
-            if ch == '#' {
-                self.trees.insert((self.length, index));
+            match ch {
+                '#' => { self.trees.insert((self.length, index)); },
+                '.' => (),
+                _ => return Err(ParseError::new(index + 1, "expected '#' or '.'"))
             }
         }
         self.length += 1;
-        self.period = cmp::max(self.period, line.chars().count())
+        self.period = cmp::max(self.period, line.chars().count());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+
+    const EXAMPLE : &str = "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n\
+                            ..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n\
+                            .#..#...#.#";
+
+    #[test]
+    fn example() {
+        let map = Map::from_lines(EXAMPLE.lines().map(|line| line.to_string())).unwrap();
+        let counts : Vec<usize> = [(1, 1), (1, 3), (1, 5), (1, 7), (2, 1)].iter().map(
+            |&(i_step, j_step)| map.count_trees_path(i_step, j_step)
+        ).collect();
+        assert_eq!(counts, vec!(2, 7, 3, 4, 2));
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = vec!("..#".to_string(), ".x.".to_string());
+        let err = Map::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 2));
     }
-}
\ No newline at end of file
+}
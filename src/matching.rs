@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use super::error::ParseError;
+
+#[derive(Clone)]
 pub enum Rule {
     Lit(String),
     Extern(Vec<usize>),
@@ -8,6 +11,7 @@ pub enum Rule {
     Bracket(usize, usize)
 }
 
+#[derive(Clone)]
 pub struct RuleSet {
     rules : HashMap<usize, Rule>
 }
@@ -22,50 +26,63 @@ impl Rule {
         }
     }
 
-    fn try_pipe_from_string(string : &str) -> Option<Rule> {
+    fn try_pipe_from_string(string : &str) -> Option<Result<Rule, ParseError>> {
         string.split_once(" | ").map(
-            |(left, right)| 
+            |(left, right)| Ok(
                 Rule::Pipe(
-                    Box::new(Rule::from_string(left)), 
-                    Box::new(Rule::from_string(right))
+                    Box::new(Rule::from_string(left).map_err(|err| err.within(string, left))?),
+                    Box::new(Rule::from_string(right).map_err(|err| err.within(string, right))?)
                 )
+            )
         )
     }
 
-    fn extern_from_string(string : &str) -> Rule {
-        let indices = string.split(" ").filter_map(
-            |part| part.parse::<usize>().ok()
-        ).collect();
-        Rule::Extern(indices)
+    fn extern_from_string(string : &str) -> Result<Rule, ParseError> {
+        let indices = string.split(" ").map(
+            |part| part.parse::<usize>().map_err(
+                |_| ParseError::at(string, part, "expected a rule number")
+            )
+        ).collect::<Result<Vec<usize>, ParseError>>()?;
+        Ok(Rule::Extern(indices))
     }
 
-    fn from_string(string : &str) -> Rule {
+    fn from_string(string : &str) -> Result<Rule, ParseError> {
         Rule::try_pipe_from_string(string).or(
-            Rule::try_char_from_string(string)
-        ).unwrap_or(
-            Rule::extern_from_string(string)
+            Rule::try_char_from_string(string).map(Ok)
+        ).unwrap_or_else(
+            || Rule::extern_from_string(string)
         )
     }
 
-    pub fn from_line(string : &str) -> Option<(usize, Rule)> {
-        string.split_once(": ").and_then(
-            |(num_str, rule_str)| num_str.parse::<usize>().ok().map(
-                |num| (num, Rule::from_string(rule_str))
-            )
-        )
+    pub fn from_line(string : &str) -> Result<(usize, Rule), ParseError> {
+        let (num_str, rule_str) = string.split_once(": ").ok_or(
+            ParseError::new(1, "expected '<number>: <rule>'")
+        )?;
+        let num = num_str.parse::<usize>().map_err(
+            |_| ParseError::new(1, "expected a rule number")
+        )?;
+        let rule = Rule::from_string(rule_str).map_err(
+            |err| err.within(string, rule_str)
+        )?;
+        Ok((num, rule))
     }
 }
 
 impl RuleSet {
-    pub fn from_lines<I>(lines : I) -> RuleSet
+    pub fn from_lines<I>(lines : I) -> Result<RuleSet, ParseError>
       where I : Iterator<Item = String>
     {
-        let rules = lines.filter_map(
-            |line| Rule::from_line(&line)
-        ).collect();
-        RuleSet { rules : rules } 
+        let mut rules = HashMap::new();
+        for (index, line) in lines.enumerate() {
+            let (num, rule) = Rule::from_line(&line).map_err(
+                |err| err.offset_lines(index)
+            )?;
+            rules.insert(num, rule);
+        }
+        Ok(RuleSet { rules : rules })
     }
 
+    #[allow(clippy::manual_while_let_some, clippy::map_flatten)]
     fn sub_matches_rule<'a>(self : &Self, string : &'a str, rule : &Rule) -> Vec<&'a str> {
         match rule {
             Rule::Lit(lit) => string.strip_prefix(lit).into_iter().collect(),
@@ -126,6 +143,7 @@ impl RuleSet {
         }
     }
 
+    #[allow(clippy::map_flatten)]
     fn repeat_sub_matches<'a>(self : &Self, string : &'a str, index : usize, repeat : usize) -> Vec<&'a str> {
         let mut matches : Vec<&'a str> = vec!(string);
         for _ in 0..repeat {
@@ -140,6 +158,7 @@ impl RuleSet {
         self.sub_matches_rule(string, self.rules.get(&index).unwrap())
     }
 
+    #[allow(clippy::len_zero)]
     pub fn is_match(self : &Self, string : &str) -> bool {
         self.sub_matches(string, 0).iter().filter(
             |rest| rest.len() == 0
@@ -152,4 +171,68 @@ impl RuleSet {
     }
 
     
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rule;
+    use super::RuleSet;
+
+    fn rules(text : &str) -> RuleSet {
+        RuleSet::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let rules = rules("0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"");
+        let matched : Vec<bool> = ["ababbb", "bababa", "abbbab", "aaabbb", "aaaabbb"].iter().map(
+            |message| rules.is_match(message)
+        ).collect();
+        assert_eq!(matched, vec!(true, false, true, false, false));
+    }
+
+    #[test]
+    fn looping_rules() {
+        let mut rules = rules(
+            "42: 9 14 | 10 1\n9: 14 27 | 1 26\n10: 23 14 | 28 1\n1: \"a\"\n11: 42 31\n\
+             5: 1 14 | 15 1\n19: 14 1 | 14 14\n12: 24 14 | 19 1\n16: 15 1 | 14 14\n\
+             31: 14 17 | 1 13\n6: 14 14 | 1 14\n2: 1 24 | 14 4\n0: 8 11\n13: 14 3 | 1 12\n\
+             15: 1 | 14\n17: 14 2 | 1 7\n23: 25 1 | 22 14\n28: 16 1\n4: 1 1\n20: 14 14 | 1 15\n\
+             3: 5 14 | 16 1\n27: 1 6 | 14 18\n14: \"b\"\n21: 14 1 | 1 14\n25: 1 1 | 1 14\n\
+             22: 14 14\n8: 42\n26: 14 22 | 1 20\n18: 15 15\n7: 14 5 | 1 21\n24: 14 1"
+        );
+        let messages = [
+            "abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa",
+            "bbabbbbaabaabba",
+            "babbbbaabbbbbabbbbbbaabaaabaaa",
+            "aaabbbbbbaaaabaababaabababbabaaabbababababaaa",
+            "bbbbbbbaaaabbbbaaabbabaaa",
+            "bbbababbbbaaaaaaaabbababaaababaabab",
+            "ababaaaaaabaaab",
+            "ababaaaaabbbaba",
+            "baabbaaaabbaaaababbaababb",
+            "abbbbabbbbaaaababbbbbbaaaababb",
+            "aaaaabbaabaaaaababaa",
+            "aaaabbaaaabbaaa",
+            "aaaabbaabbaaaaaaabbbabbbaaabbaabaaa",
+            "babaaabbbaaabaababbaabababaaab",
+            "aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"
+        ];
+        let count = |rules : &RuleSet| messages.iter().filter(
+            |message| rules.is_match(message)
+        ).count();
+        assert_eq!(count(&rules), 3);
+        rules.add_new_rules();
+        assert_eq!(count(&rules), 12);
+    }
+
+    #[test]
+    fn splits_on_first_separator() {
+        let (num, rule) = Rule::from_line("8: 42 | 42 8").unwrap();
+        assert_eq!(num, 8);
+        assert!(matches!(rule, Rule::Pipe(_, _)));
+        let err = Rule::from_line("8: 42 x").err().unwrap();
+        assert_eq!((err.line, err.column), (1, 7));
+        assert!(Rule::from_line("8 42").is_err());
+    }
+}
@@ -32,10 +32,31 @@ impl Iterator for Game {
 
     fn next(self : &mut Self) -> Option<u64> {
         let num = self.next_num();
-        // Update state
-        self.memory.insert(self.previous, self.index);
+        // Update state, there is no previous number before the first turn
+        if self.index > 0 {
+            self.memory.insert(self.previous, self.index);
+        }
         self.previous = num;
         self.index += 1;
         Some(num)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Game;
+
+    #[test]
+    fn example() {
+        let mut game = Game::new(vec!(0, 3, 6));
+        let start : Vec<u64> = game.by_ref().take(10).collect();
+        assert_eq!(start, vec!(0, 3, 6, 0, 3, 3, 1, 0, 4, 0));
+        assert_eq!(Game::new(vec!(0, 3, 6)).nth(2020-1), Some(436));
+    }
+
+    #[test]
+    fn start_without_zero() {
+        assert_eq!(Game::new(vec!(1, 3, 2)).nth(2020-1), Some(1));
+        assert_eq!(Game::new(vec!(3, 1, 2)).nth(2020-1), Some(1836));
+    }
+}
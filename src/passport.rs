@@ -1,9 +1,11 @@
-use std::iter::FromIterator;
 use std::collections::HashMap;
 
+use super::error::ParseError;
+
 static REQUIRED_FIELD_NAMES : [&str; 7] = ["byr","iyr","eyr","hgt","hcl","ecl","pid"];
 static VALID_EYE_COLOURS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
 
+#[allow(clippy::manual_is_ascii_check)]
 fn is_hex(string : &str) -> bool {
     string.chars().all(
         |c| ('0'..='9').contains(&c) || ('a'..='f').contains(&c)
@@ -15,17 +17,19 @@ pub struct Passport {
 }
 
 impl Passport {
-    pub fn new(data : &str) -> Passport {
-        let data = data.to_string();
-        let elements = HashMap::from_iter(
-            data.split_whitespace().filter_map(
-                |part| part.split_once(':')
-            ).map(
-                |(key, val)| (key.to_string(), val.to_string())
-            )
-        );
-        Passport { elements : elements }
+    pub fn from_string(data : &str) -> Result<Passport, ParseError> {
+        let mut elements = HashMap::new();
+        for (index, line) in data.lines().enumerate() {
+            for part in line.split_whitespace() {
+                let (key, val) = part.split_once(':').ok_or(
+                    ParseError::at(line, part, "expected '<field>:<value>'").offset_lines(index)
+                )?;
+                elements.insert(key.to_string(), val.to_string());
+            }
+        }
+        Ok(Passport { elements : elements })
     }
+    #[allow(clippy::unnecessary_to_owned)]
     pub fn required_fields_exist(self : &Self) -> bool {
         REQUIRED_FIELD_NAMES.iter().all(
             |field_name| self.elements.contains_key(&field_name.to_string())
@@ -40,6 +44,7 @@ impl Passport {
         self.ecl_valid() &&
         self.pid_valid()
     }
+    #[allow(clippy::manual_range_contains)]
     pub fn byr_valid(self : &Self) -> bool {
         self.elements.get("byr").and_then(
             |byr| byr.parse::<usize>().ok()
@@ -47,6 +52,7 @@ impl Passport {
             |byr| 1920 <= byr && byr <= 2002
         ).unwrap_or(false)
     }
+    #[allow(clippy::manual_range_contains)]
     fn iyr_valid(self : &Self) -> bool {
         self.elements.get("iyr").and_then(
             |byr| byr.parse::<usize>().ok()
@@ -54,6 +60,7 @@ impl Passport {
             |byr| 2010 <= byr && byr <= 2020
         ).unwrap_or(false)
     }
+    #[allow(clippy::manual_range_contains)]
     fn eyr_valid(self : &Self) -> bool {
         self.elements.get("eyr").and_then(
             |byr| byr.parse::<usize>().ok()
@@ -82,6 +89,7 @@ impl Passport {
             |hgt| (59..=76).contains(&hgt)
         ).unwrap_or(false)
     }
+    #[allow(clippy::redundant_closure)]
     fn hcl_valid(self : &Self) -> bool {
         self.elements.get("hcl").and_then(
             |hcl| hcl.strip_prefix('#')
@@ -99,4 +107,59 @@ impl Passport {
             |pid| pid.parse::<usize>().is_ok() && pid.len() == 9
         ).unwrap_or(false)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Passport;
+
+    fn passports(text : &str) -> Vec<Passport> {
+        text.split("\n\n").map(
+            |chunk| Passport::from_string(chunk).unwrap()
+        ).collect()
+    }
+
+    #[test]
+    fn required_fields() {
+        let data = passports(
+            "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\n\
+             iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929\n\n\
+             hcl:#ae17e1 iyr:2013\neyr:2024\necl:brn pid:760753108 byr:1931\nhgt:179cm\n\n\
+             hcl:#cfa07d eyr:2025 pid:166559648\niyr:2011 ecl:brn hgt:59in"
+        );
+        let valid : Vec<bool> = data.iter().map(
+            |passport| passport.required_fields_exist()
+        ).collect();
+        assert_eq!(valid, vec!(true, false, true, false));
+    }
+
+    #[test]
+    fn invalid_values() {
+        let data = passports(
+            "eyr:1972 cid:100\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926\n\n\
+             iyr:2019\nhcl:#602927 eyr:1967 hgt:170cm\necl:grn pid:012533040 byr:1946\n\n\
+             hcl:dab227 iyr:2012\necl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277\n\n\
+             hgt:59cm ecl:zzz\neyr:2038 hcl:74454a iyr:2023\npid:3556412378 byr:2007"
+        );
+        assert!(data.iter().all(|passport| !passport.required_fields_valid()));
+    }
+
+    #[test]
+    fn valid_values() {
+        let data = passports(
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\nhcl:#623a2f\n\n\
+             eyr:2029 ecl:blu cid:129 byr:1989\niyr:2014 pid:896056539 hcl:#a97842 hgt:165cm\n\n\
+             hcl:#888785\nhgt:164cm byr:2001 iyr:2015 cid:88\npid:545766238 ecl:hzl\neyr:2022\n\n\
+             iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719"
+        );
+        assert!(data.iter().all(|passport| passport.required_fields_valid()));
+    }
+
+    #[test]
+    fn splits_on_first_separator() {
+        let passport = Passport::from_string("hcl:#123abc:x").unwrap();
+        assert_eq!(passport.elements["hcl"], "#123abc:x");
+        let err = Passport::from_string("byr:1937\niyr:2017 hgt").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 10));
+    }
+}
@@ -1,3 +1,5 @@
+use super::error::ParseError;
+
 #[derive(Copy, Clone)]
 pub enum RuleSet {
     Sled,
@@ -8,30 +10,30 @@ struct Verification {
     max : usize,
     min : usize
 }
-fn parse_range(string : &str) -> Option<(usize, usize)> {
+fn parse_range(string : &str) -> Result<(usize, usize), ParseError> {
     let parts = string.split_once('-').map(
         |(min, max)| (min.parse::<usize>(), max.parse::<usize>())
     );
     match parts {
-        Some((Ok(min), Ok(max))) => Some((min, max)),
-        _ => None
+        // Positions count from 1, so max >= min >= 1 also rules out max == 0
+        Some((Ok(min), Ok(max))) if min >= 1 && max >= min => Ok((min, max)),
+        _ => Err(ParseError::new(1, "expected a range '<min>-<max>'"))
     }
 }
 impl Verification {
-    pub fn from_string(string : &str) -> Option<Verification> {
-        let parts = string.split_once(' ').map(
-            |(range, ch)| (parse_range(range), ch.chars().next())
-        );
-        match parts {
-            Some((Some((min, max)), Some(ch))) => Some(
-                Verification {
-                    letter : ch,
-                    max : max,
-                    min : min
-                }
-            ),
-            _ => None
-        }
+    pub fn from_string(string : &str) -> Result<Verification, ParseError> {
+        let (range, ch) = string.split_once(' ').ok_or(
+            ParseError::new(1, "expected '<min>-<max> <letter>'")
+        )?;
+        let (min, max) = parse_range(range)?;
+        let letter = ch.chars().next().ok_or(
+            ParseError::at(string, ch, "expected a letter")
+        )?;
+        Ok(Verification {
+            letter : letter,
+            max : max,
+            min : min
+        })
     }
     pub fn verify(self : &Self, string : &str, rules : RuleSet) -> bool {
         match rules {
@@ -64,22 +66,68 @@ impl Database {
     pub fn new() -> Database {
         Database { passwords : Vec::new() }
     }
-    pub fn add_line(self : &mut Self, line : &str) {
-        let parts = line.split_once(':').map(
-            |(validation, password)| (
-                Verification::from_string(validation), 
-                password.strip_prefix(" ")
-            )
-        );
-        // Add if everything valid
-        match parts {
-            Some((Some(validation), Some(password))) => self.passwords.push((validation, password.to_string())),
-            _ => ()
+    pub fn from_lines<I>(lines : I) -> Result<Database, ParseError>
+      where I : Iterator<Item = String>
+    {
+        let mut database = Database::new();
+        for (index, line) in lines.enumerate() {
+            database.add_line(&line).map_err(
+                |err| err.offset_lines(index)
+            )?;
         }
+        Ok(database)
+    }
+    pub fn add_line(self : &mut Self, line : &str) -> Result<(), ParseError> {
+        let (validation, rest) = line.split_once(':').ok_or(
+            ParseError::new(1, "expected '<policy>: <password>'")
+        )?;
+        let verification = Verification::from_string(validation)?;
+        let password = rest.strip_prefix(" ").ok_or(
+            ParseError::at(line, rest, "expected a space before the password")
+        )?;
+        self.passwords.push((verification, password.to_string()));
+        Ok(())
     }
     pub fn count_valid(self : &Self, rules : RuleSet) -> usize {
         self.passwords.iter().filter(
             |(verification, password)| verification.verify(password, rules)
         ).count()
     }
-    }
\ No newline at end of file
+    }
+
+#[cfg(test)]
+mod tests {
+    use super::Database;
+    use super::RuleSet;
+
+    fn database(text : &str) -> Database {
+        Database::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let data = database("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc");
+        assert_eq!(data.count_valid(RuleSet::Sled), 2);
+        assert_eq!(data.count_valid(RuleSet::Toboggan), 1);
+    }
+
+    #[test]
+    fn splits_on_first_separator() {
+        // Only the first ':' ends the policy, the password may contain more
+        let data = database("2-3 a: b:aa");
+        assert_eq!(data.count_valid(RuleSet::Sled), 1);
+        assert_eq!(data.count_valid(RuleSet::Toboggan), 1);
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = vec!("1-3 a: abcde".to_string(), "1-3 a abcde".to_string());
+        let err = Database::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(Database::from_lines(vec!("0-3 a: abc".to_string()).into_iter()).is_err());
+        assert!(Database::from_lines(vec!("1-0 a: abc".to_string()).into_iter()).is_err());
+        assert!(Database::from_lines(vec!("3-2 a: abc".to_string()).into_iter()).is_err());
+        assert_eq!(database("2-2 a: aab").count_valid(RuleSet::Toboggan), 0);
+        assert!(Database::from_lines(vec!("1-3 a:abc".to_string()).into_iter()).is_err());
+    }
+}
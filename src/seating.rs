@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use super::error::ParseError;
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum SeatState {
     Floor,
@@ -11,34 +13,40 @@ pub enum SeatingRules {
     Visible
 }
 impl SeatState {
-    fn from_char(ch : char) -> SeatState {
+    fn from_char(ch : char) -> Option<SeatState> {
         match ch {
-            'L' => SeatState::Empty,
-            '#' => SeatState::Occupied,
-            _ => SeatState::Floor
+            'L' => Some(SeatState::Empty),
+            '#' => Some(SeatState::Occupied),
+            '.' => Some(SeatState::Floor),
+            _ => None
         }
     }
 }
+#[derive(Clone)]
 pub struct Seating {
     seats : HashMap<(i32, i32), SeatState>
 }
 impl Seating {
-    pub fn from_lines<I>(lines : I) -> Seating 
+    pub fn from_lines<I>(lines : I) -> Result<Seating, ParseError> 
       where I : Iterator<Item = String> 
     {
         let mut seats : HashMap<(i32, i32), SeatState> = HashMap::new();
         for (i, line) in lines.enumerate() {
             for (j, ch) in line.chars().enumerate() {
-                seats.insert((i as i32, j as i32) , SeatState::from_char(ch));
+                let state = SeatState::from_char(ch).ok_or(
+                    ParseError::new(j + 1, "expected 'L', '#' or '.'").offset_lines(i)
+                )?;
+                seats.insert((i as i32, j as i32) , state);
             }
         }
-        Seating { seats : seats }
+        Ok(Seating { seats : seats })
     }
     fn is_occupied(self : &Self, seat : (i32, i32)) -> bool {
         self.seats.get(&seat).map(
             |&state| state == SeatState::Occupied
         ).unwrap_or(false)
     }
+    #[allow(clippy::collapsible_if)]
     fn surrounding_occupied(self : &Self, (seat_i,seat_j) : &(i32, i32)) -> usize {
         let mut count : usize = 0;
         for i in -1..=1 {
@@ -52,6 +60,7 @@ impl Seating {
         }
         count
     }
+    #[allow(clippy::filter_next)]
     fn is_direction_occupied(self : &Self, (dir_i,dir_j) : (i32, i32), (seat_i,seat_j) : &(i32, i32)) -> bool {
         (1..).map(
             |k| (seat_i + (k*dir_i), seat_j + (k*dir_j))
@@ -63,6 +72,7 @@ impl Seating {
             |&state| state == SeatState::Occupied
         ).unwrap_or(false)
     }
+    #[allow(clippy::collapsible_if)]
     fn visible_occupied(self : &Self, seat : &(i32, i32)) -> usize {
         let mut count : usize = 0;
         for i in -1..=1 {
@@ -141,4 +151,48 @@ impl Seating {
         ).count()
  
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Seating;
+    use super::SeatingRules;
+
+    const EXAMPLE : &str = "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL\n\
+                            L.LLLLL.LL\n..L.L.....\nLLLLLLLLLL\nL.LLLLLL.L\nL.LLLLL.LL";
+
+    fn seating() -> Seating {
+        Seating::from_lines(EXAMPLE.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn adjacent() {
+        let mut data = seating();
+        data.simulate(&SeatingRules::Adjacent);
+        assert_eq!(data.number_occupied(), 37);
+    }
+
+    #[test]
+    fn visible() {
+        let mut data = seating();
+        data.simulate(&SeatingRules::Visible);
+        assert_eq!(data.number_occupied(), 26);
+    }
+
+    #[test]
+    fn visible_stops_at_edge() {
+        // Seats see each other across the floor and nothing past the edge
+        let mut data = Seating::from_lines(vec!("L.L".to_string()).into_iter()).unwrap();
+        assert!(data.simulate_once(&SeatingRules::Visible));
+        assert_eq!(data.number_occupied(), 2);
+        assert!(!data.simulate_once(&SeatingRules::Visible));
+        assert_eq!(data.number_occupied(), 2);
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = vec!("L.L".to_string(), "L.x".to_string());
+        let err = Seating::from_lines(lines.into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+}
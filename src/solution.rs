@@ -0,0 +1,100 @@
+use std::fmt;
+
+use super::error::Error;
+use super::io::Source;
+use super::json as json;
+
+// The answer to one part of a puzzle, with any intermediate values behind it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Answer {
+    value : String,
+    details : Vec<(String, String)>
+}
+
+impl Answer {
+    pub fn new<T : fmt::Display>(value : T) -> Answer {
+        Answer { value : value.to_string(), details : Vec::new() }
+    }
+
+    pub fn with_detail<T : fmt::Display>(mut self : Self, name : &str, value : T) -> Answer {
+        self.details.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn value(self : &Self) -> &str {
+        &self.value
+    }
+
+    pub fn details(self : &Self) -> &[(String, String)] {
+        &self.details
+    }
+
+    // Values are written as strings so large answers survive JSON readers
+    pub fn details_json(self : &Self) -> String {
+        let fields : Vec<(&str, String)> = self.details.iter().map(
+            |(name, value)| (name.as_str(), json::string(value))
+        ).collect();
+        json::object(&fields)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(self : &Self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+// A day's puzzle, parsed from its input and then solved part by part
+pub trait Solution {
+    fn parse(source : &Source) -> Result<Self, Error>
+      where Self : Sized;
+
+    // Parts fail only on input that parsed but can not be solved
+    fn part1(self : &Self) -> Result<Answer, Error>;
+
+    // Day 25 only has the one part
+    fn part2(self : &Self) -> Result<Option<Answer>, Error> {
+        Ok(None)
+    }
+
+    fn solve(self : &Self, part : u8) -> Result<Option<Answer>, Error> {
+        match part {
+            1 => self.part1().map(Some),
+            2 => self.part2(),
+            _ => Ok(None)
+        }
+    }
+}
+
+fn parse_boxed<S>(source : &Source) -> Result<Box<dyn Solution>, Error>
+  where S : Solution + 'static
+{
+    Ok(Box::new(S::parse(source)?))
+}
+
+// A day in the registry, able to parse its input into a solution
+pub struct Entry {
+    pub day : i8,
+    parse : fn(&Source) -> Result<Box<dyn Solution>, Error>
+}
+
+impl Entry {
+    pub fn new<S>(day : i8) -> Entry
+      where S : Solution + 'static
+    {
+        Entry {
+            day : day,
+            parse : parse_boxed::<S>
+        }
+    }
+
+    pub fn parse(self : &Self, source : &Source) -> Result<Box<dyn Solution>, Error> {
+        (self.parse)(source)
+    }
+}
+
+pub fn find(registry : &[Entry], day : i8) -> Option<&Entry> {
+    registry.iter().find(
+        |entry| entry.day == day
+    )
+}
@@ -1,27 +1,41 @@
 use std::collections::HashSet;
 
-fn from_names_binary(zero : char, string : &str) -> Option<usize> {
-    let converted : String = string.chars().map(
-        |c| if c==zero {'0'} else {'1'}
+use super::error::ParseError;
+
+fn from_names_binary(zero : char, one : char, string : &str) -> Option<usize> {
+    let converted : Option<String> = string.chars().map(
+        |c| if c==zero {
+            Some('0')
+        } else if c==one {
+            Some('1')
+        } else {
+            None
+        }
     ).collect();
-    usize::from_str_radix(&converted, 2).ok()
+    converted.and_then(
+        |converted| usize::from_str_radix(&converted, 2).ok()
+    )
 } 
 pub struct Seat {
     row : usize,
     col : usize
 }
 impl Seat {
-    pub fn from_string(string : &str) -> Option<Seat> {
-        let row_opt = string.get(..7).and_then(
-            |row| from_names_binary('F', row)
-        );
-        let col_opt = string.get(7..).and_then(
-            |col| from_names_binary('L', col)
-        );
-        match (row_opt, col_opt) {
-            (Some(row), Some(col)) => Some(Seat {row: row, col : col}),
-            _ => None
-        } 
+    pub fn from_string(string : &str) -> Result<Seat, ParseError> {
+        if string.len() != 10 {
+            return Err(ParseError::new(1, "expected 10 characters"));
+        }
+        let row = string.get(..7).and_then(
+            |row| from_names_binary('F', 'B', row)
+        ).ok_or(
+            ParseError::new(1, "expected a row of 'F' and 'B'")
+        )?;
+        let col = string.get(7..).and_then(
+            |col| from_names_binary('L', 'R', col)
+        ).ok_or(
+            ParseError::new(8, "expected a column of 'L' and 'R'")
+        )?;
+        Ok(Seat {row: row, col : col})
     }
     pub fn id(self : &Self) -> usize {
         self.row * 8 + self.col
@@ -50,4 +64,34 @@ impl Plane {
             |num| !self.seats.contains(num)
         ).collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Plane;
+    use super::Seat;
+
+    #[test]
+    fn seat_ids() {
+        let ids : Vec<usize> = ["FBFBBFFRLR", "BFFFBBFRRR", "FFFBBBFRRR", "BBFFBBFRLL"].iter().map(
+            |pass| Seat::from_string(pass).unwrap().id()
+        ).collect();
+        assert_eq!(ids, vec!(357, 567, 119, 820));
+    }
+
+    #[test]
+    fn missing_seat() {
+        let mut plane = Plane::new();
+        for pass in ["FFFFFFFLLL", "FFFFFFFLLR", "FFFFFFFLRR"].iter() {
+            plane.add_seat(&Seat::from_string(pass).unwrap());
+        }
+        assert_eq!(plane.max(), 3);
+        assert_eq!(plane.find_missing(), vec!(2));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Seat::from_string("FBFBBFFRL").err().unwrap().column, 1);
+        assert_eq!(Seat::from_string("FBFBBFFRLB").err().unwrap().column, 8);
+    }
+}
@@ -1,30 +1,51 @@
 use std::collections::HashMap;
 
+use super::error::ParseError;
+
+#[derive(Clone)]
 pub struct Rule {
     pub name : String,
     ranges : Vec<(u64,u64)>
 }
 
-fn parse_range(string : &str) -> Option<(u64, u64)> {
+fn parse_range(string : &str) -> Result<(u64, u64), ParseError> {
     string.split_once("-").and_then(
         |(min_str, max_str)| min_str.parse::<u64>().ok().and_then(
             |min| max_str.parse::<u64>().ok().map(
                 |max| (min, max)
             )
         )
+    ).ok_or(
+        ParseError::new(1, "expected a range '<min>-<max>'")
     )
 }
 
-impl Rule {
-    pub fn from_string(string : &str) -> Option<Rule> {
-        string.split_once(": ").map(
-            |(name, ranges_str)| {
-                Rule {
-                    name : name.to_string(),
-                    ranges : ranges_str.split(" or ").filter_map(parse_range).collect()
-                }
-            }
+// Parse the lines of a section, the first line of the section being first_line
+fn parse_section<'a, I, T, F>(lines : I, first_line : usize, parse : F) -> Result<Vec<T>, ParseError>
+  where I : Iterator<Item = &'a str>,
+        F : Fn(&str) -> Result<T, ParseError>
+{
+    lines.enumerate().map(
+        |(index, line)| parse(line).map_err(
+            |err| err.offset_lines(first_line + index)
         )
+    ).collect()
+}
+
+impl Rule {
+    pub fn from_string(string : &str) -> Result<Rule, ParseError> {
+        let (name, ranges_str) = string.split_once(": ").ok_or(
+            ParseError::new(1, "expected '<name>: <ranges>'")
+        )?;
+        let ranges = ranges_str.split(" or ").map(
+            |range| parse_range(range).map_err(
+                |err| err.within(string, range)
+            )
+        ).collect::<Result<Vec<(u64, u64)>, ParseError>>()?;
+        Ok(Rule {
+            name : name.to_string(),
+            ranges : ranges
+        })
     }
 
     pub fn valid(self : &Self, val : u64) -> bool {
@@ -34,19 +55,24 @@ impl Rule {
     }
 }
 
+#[derive(Clone)]
 pub struct Ticket {
     fields : Vec<u64>
 }
 
 impl Ticket {
-    pub fn from_string(string : &str) -> Ticket {
-        Ticket {
-            fields : string.split(',').filter_map(
-                |part| part.parse::<u64>().ok()
-            ).collect()
-        }
+    pub fn from_string(string : &str) -> Result<Ticket, ParseError> {
+        let fields = string.split(',').map(
+            |part| part.parse::<u64>().map_err(
+                |_| ParseError::at(string, part, "expected a field value")
+            )
+        ).collect::<Result<Vec<u64>, ParseError>>()?;
+        Ok(Ticket {
+            fields : fields
+        })
     }
 
+    #[allow(clippy::ptr_arg)]
     pub fn num_invalid_fields(self : &Self, rules : &Vec<Rule>) -> u64 {
         self.fields.iter().filter(
             |val| rules.iter().all(
@@ -64,6 +90,7 @@ impl Ticket {
     }
 }
 
+#[derive(Clone)]
 pub struct ScanningResult {
     rules : Vec<Rule>,
     own_ticket : Ticket,
@@ -71,15 +98,27 @@ pub struct ScanningResult {
 }
 
 impl ScanningResult {
-    pub fn from_lines<'a, I, J>(rules_lines : I, own_ticket_str : &str, other_tickets_lines : J) -> ScanningResult 
-      where I : Iterator<Item = &'a str>, 
-            J : Iterator<Item = &'a str>  
-    {
-        ScanningResult {
-            rules : rules_lines.filter_map(Rule::from_string).collect(),
-            own_ticket : Ticket::from_string(own_ticket_str),
-            other_tickets : other_tickets_lines.map(Ticket::from_string).collect()
+    // The rules, "your ticket:" and "nearby tickets:" sections separated by blank lines
+    pub fn from_string(string : &str) -> Result<ScanningResult, ParseError> {
+        let chunks : Vec<&str> = string.split("\n\n").collect();
+        if chunks.len() != 3 {
+            return Err(ParseError::new(1, "expected rules, your ticket and nearby tickets"));
         }
+        let own_start = chunks[0].lines().count() + 1;
+        let others_start = own_start + chunks[1].lines().count() + 1;
+        let own_ticket_str = chunks[1].lines().nth(1).ok_or(
+            ParseError::new(1, "expected your ticket").offset_lines(own_start)
+        )?;
+        let rules = parse_section(chunks[0].lines(), 0, Rule::from_string)?;
+        let own_ticket = Ticket::from_string(own_ticket_str).map_err(
+            |err| err.offset_lines(own_start + 1)
+        )?;
+        let other_tickets = parse_section(chunks[2].lines().skip(1), others_start + 1, Ticket::from_string)?;
+        Ok(ScanningResult {
+            rules : rules,
+            own_ticket : own_ticket,
+            other_tickets : other_tickets
+        })
     }
 
     pub fn scanning_error_rate(self : &Self) -> u64 {
@@ -143,4 +182,41 @@ impl ScanningResult {
             |index| self.own_ticket.field(*index)
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScanningResult;
+
+    #[test]
+    fn scanning_error_rate() {
+        let data = ScanningResult::from_string(
+            "class: 1-3 or 5-7\nrow: 6-11 or 33-44\nseat: 13-40 or 45-50\n\n\
+             your ticket:\n7,1,14\n\n\
+             nearby tickets:\n7,3,47\n40,4,50\n55,2,20\n38,6,12"
+        ).unwrap();
+        assert_eq!(data.scanning_error_rate(), 71);
+    }
+
+    #[test]
+    fn own_fields() {
+        let mut data = ScanningResult::from_string(
+            "class: 0-1 or 4-19\nrow: 0-5 or 8-19\nseat: 0-13 or 16-19\n\n\
+             your ticket:\n11,12,13\n\n\
+             nearby tickets:\n3,9,18\n15,1,5\n5,14,9"
+        ).unwrap();
+        data.discard_invalid();
+        assert_eq!(data.own_field("class"), Some(12));
+        assert_eq!(data.own_field("row"), Some(11));
+        assert_eq!(data.own_field("seat"), Some(13));
+        assert!(data.departures().is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        let err = ScanningResult::from_string(
+            "class: 1-3 or 5-7\n\nyour ticket:\n7,1\n\nnearby tickets:\n7,3\n7,x"
+        ).err().unwrap();
+        assert_eq!((err.line, err.column), (8, 3));
+    }
+}
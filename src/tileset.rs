@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use super::error::ParseError;
+
 struct Tile {
     id : u64,
     data : Vec<Vec<bool>>
@@ -24,7 +26,7 @@ enum Side {
 }
 
 #[derive(Debug)]
-pub enum Transform {
+enum Transform {
     Id,
     FlipX,
     FlipY,
@@ -36,7 +38,7 @@ pub enum Transform {
 }
 
 impl Transform {
-    pub fn all() -> Vec<Transform> {
+    fn all() -> Vec<Transform> {
         vec!(
             Transform::Id,
             Transform::FlipX,
@@ -49,7 +51,7 @@ impl Transform {
         )
     }
 
-    pub fn transform(self : &Self, i : i64, j : i64) -> (i64, i64) {
+    fn transform(self : &Self, i : i64, j : i64) -> (i64, i64) {
         match self {
             Transform::Id => (i,j),
             Transform::FlipX => (-i, j),
@@ -70,6 +72,7 @@ pub struct TileSolution<'a> {
     tiles : HashMap<(i64, i64), Tile>
 }
 
+#[allow(clippy::ptr_arg)]
 fn flip_edge(edge : &Vec<bool>) -> Vec<bool> {
     edge.iter().rev().cloned().collect()
 }
@@ -84,18 +87,35 @@ fn normalized_edge(edge : &Vec<bool>) -> Vec<bool> {
 }
 
 impl Tile {
-    pub fn from_string(string : &str) -> Tile {
+    pub fn from_string(string : &str) -> Result<Tile, ParseError> {
         let mut line_iter = string.lines();
-        let id = line_iter.next().unwrap()
-                          .strip_prefix("Tile ").unwrap()
-                          .strip_suffix(":").unwrap()
-                          .parse::<u64>().unwrap();
-        let data : Vec<Vec<bool>> = line_iter.map(
-            |line| line.chars().map(
-                |ch| ch == '#'
-            ).collect()
-        ).collect();
-        Tile { id : id, data : data }
+        let id = line_iter.next().and_then(
+            |line| line.strip_prefix("Tile ")
+        ).and_then(
+            |line| line.strip_suffix(":")
+        ).and_then(
+            |id| id.parse::<u64>().ok()
+        ).ok_or(
+            ParseError::new(1, "expected 'Tile <id>:'")
+        )?;
+        let mut data : Vec<Vec<bool>> = Vec::new();
+        for (i, line) in line_iter.enumerate() {
+            let row = line.chars().enumerate().map(
+                |(j, ch)| match ch {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(ParseError::new(j + 1, "expected '#' or '.'").offset_lines(i + 1))
+                }
+            ).collect::<Result<Vec<bool>, ParseError>>()?;
+            if row.len() != 10 {
+                return Err(ParseError::new(1, "expected 10 pixels").offset_lines(i + 1));
+            }
+            data.push(row);
+        }
+        if data.len() != 10 {
+            return Err(ParseError::new(1, "expected 10 rows of pixels"));
+        }
+        Ok(Tile { id : id, data : data })
     }
 
     pub fn pixel(self : &Self, i : usize, j : usize) -> bool {
@@ -119,6 +139,7 @@ impl Tile {
         vec!(top, bottom, left, right)
     }
 
+    #[allow(clippy::redundant_closure)]
     pub fn normalized_edges(self : &Self) -> Vec<Vec<bool>> {
         self.edges().iter().map(
             |edge| normalized_edge(edge)
@@ -179,12 +200,17 @@ impl Tile {
 }
 
 impl TileSet {
-    pub fn from_string(string : &str) -> TileSet {
-        let tiles : HashMap<u64, Tile> = string.split("\n\n").map(
-            |chunk| Tile::from_string(chunk)
-        ).map(
-            |tile| (tile.id, tile)
-        ).collect();
+    #[allow(clippy::unwrap_or_default)]
+    pub fn from_string(string : &str) -> Result<TileSet, ParseError> {
+        let mut tiles : HashMap<u64, Tile> = HashMap::new();
+        let mut chunk_start = 0;
+        for chunk in string.split("\n\n") {
+            let tile = Tile::from_string(chunk).map_err(
+                |err| err.offset_lines(chunk_start)
+            )?;
+            tiles.insert(tile.id, tile);
+            chunk_start += chunk.lines().count() + 1;
+        }
         let mut normalized_edge_counts : HashMap<Vec<bool>, Vec<u64>> 
             = HashMap::new();
         for tile in tiles.values() {
@@ -192,10 +218,10 @@ impl TileSet {
                 normalized_edge_counts.entry(edge).or_insert(Vec::new()).push(tile.id);
             }
         }
-        TileSet{
+        Ok(TileSet{
             tiles : tiles,
             normalized_edge_counts : normalized_edge_counts
-        }
+        })
     }
 
     pub fn tile_class(self : &Self, class : usize) -> Vec<u64> {
@@ -240,12 +266,14 @@ impl TileSet {
         ).unwrap()
     }
 
+    #[allow(clippy::manual_contains)]
     fn tile_has_edge(self : &Self, tile_id : u64, edge : &Vec<bool>) -> bool {
         self.normalized_edge_counts[&normalized_edge(edge)].iter().any(
             |id| *id == tile_id
         )
     }
 
+    #[allow(clippy::unnecessary_unwrap)]
     fn find_top_edge(self : &Self, left : &Vec<bool>, exclude : &HashSet<u64>) -> Tile {
         for edge_tile_id in self.tile_class(1) {
             if self.tile_has_edge(edge_tile_id, left) {
@@ -262,6 +290,7 @@ impl TileSet {
         unreachable!();
     }
 
+    #[allow(clippy::unnecessary_unwrap)]
     fn find_left_edge(self : &Self, top : &Vec<bool>, exclude : &HashSet<u64>) -> Tile {
         for edge_tile_id in self.tile_class(1) {
             if self.tile_has_edge(edge_tile_id, top) {
@@ -292,6 +321,7 @@ impl TileSet {
     }
 
 
+    #[allow(clippy::needless_borrow)]
     pub fn solve<'a>(self : &'a Self, size : usize) -> TileSolution<'a> {
         let mut solution = TileSolution::new(&self, size);
         solution.solve();
@@ -426,4 +456,140 @@ impl Picture {
     fn get(self : &Self, i : i64, j : i64) -> bool {
         *self.data.get(&(i,j)).unwrap_or(&false)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::TileSet;
+
+    const EXAMPLE : &str = "Tile 2311:\n\
+                            ..##.#..#.\n\
+                            ##..#.....\n\
+                            #...##..#.\n\
+                            ####.#...#\n\
+                            ##.##.###.\n\
+                            ##...#.###\n\
+                            .#.#.#..##\n\
+                            ..#....#..\n\
+                            ###...#.#.\n\
+                            ..###..###\n\
+                            \n\
+                            Tile 1951:\n\
+                            #.##...##.\n\
+                            #.####...#\n\
+                            .....#..##\n\
+                            #...######\n\
+                            .##.#....#\n\
+                            .###.#####\n\
+                            ###.##.##.\n\
+                            .###....#.\n\
+                            ..#.#..#.#\n\
+                            #...##.#..\n\
+                            \n\
+                            Tile 1171:\n\
+                            ####...##.\n\
+                            #..##.#..#\n\
+                            ##.#..#.#.\n\
+                            .###.####.\n\
+                            ..###.####\n\
+                            .##....##.\n\
+                            .#...####.\n\
+                            #.##.####.\n\
+                            ####..#...\n\
+                            .....##...\n\
+                            \n\
+                            Tile 1427:\n\
+                            ###.##.#..\n\
+                            .#..#.##..\n\
+                            .#.##.#..#\n\
+                            #.#.#.##.#\n\
+                            ....#...##\n\
+                            ...##..##.\n\
+                            ...#.#####\n\
+                            .#.####.#.\n\
+                            ..#..###.#\n\
+                            ..##.#..#.\n\
+                            \n\
+                            Tile 1489:\n\
+                            ##.#.#....\n\
+                            ..##...#..\n\
+                            .##..##...\n\
+                            ..#...#...\n\
+                            #####...#.\n\
+                            #..#.#.#.#\n\
+                            ...#.#.#..\n\
+                            ##.#...##.\n\
+                            ..##.##.##\n\
+                            ###.##.#..\n\
+                            \n\
+                            Tile 2473:\n\
+                            #....####.\n\
+                            #..#.##...\n\
+                            #.##..#...\n\
+                            ######.#.#\n\
+                            .#...#.#.#\n\
+                            .#########\n\
+                            .###.#..#.\n\
+                            ########.#\n\
+                            ##...##.#.\n\
+                            ..###.#.#.\n\
+                            \n\
+                            Tile 2971:\n\
+                            ..#.#....#\n\
+                            #...###...\n\
+                            #.#.###...\n\
+                            ##.##..#..\n\
+                            .#####..##\n\
+                            .#..####.#\n\
+                            #..#.#..#.\n\
+                            ..####.###\n\
+                            ..#.#.###.\n\
+                            ...#.#.#.#\n\
+                            \n\
+                            Tile 2729:\n\
+                            ...#.#.#.#\n\
+                            ####.#....\n\
+                            ..#.#.....\n\
+                            ....#..#.#\n\
+                            .##..##.#.\n\
+                            .#.####...\n\
+                            ####.#.#..\n\
+                            ##.####...\n\
+                            ##..#.##..\n\
+                            #.##...##.\n\
+                            \n\
+                            Tile 3079:\n\
+                            #.#.#####.\n\
+                            .#..######\n\
+                            ..#.......\n\
+                            ######....\n\
+                            ####.#..#.\n\
+                            .#...#.##.\n\
+                            #.#####.##\n\
+                            ..#.###...\n\
+                            ..#.......\n\
+                            ..#.###...";
+
+    #[test]
+    fn corner_tiles() {
+        let tileset = TileSet::from_string(EXAMPLE).unwrap();
+        let mut corners = tileset.solve(3).corner_tiles();
+        corners.sort();
+        assert_eq!(corners, vec!(1171, 1951, 2971, 3079));
+        assert_eq!(corners.iter().product::<u64>(), 20899048083289);
+    }
+
+    #[test]
+    fn search_monster() {
+        let tileset = TileSet::from_string(EXAMPLE).unwrap();
+        let picture = tileset.solve(3).picture();
+        assert_eq!(picture.search_monster().len(), 30);
+        assert_eq!(picture.total() - picture.search_monster().len(), 273);
+    }
+
+    #[test]
+    fn malformed() {
+        let err = TileSet::from_string("Tile 1:\n..........\n.........").err().unwrap();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert!(TileSet::from_string("Tile x:\n..........").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::error::Error;
+use super::error::ParseError;
+use super::io::Source;
+use super::solution::Entry;
+
+// Known answers, one "<day> <part> <answer>" per line with # for comments
+pub struct Expected {
+    answers : BTreeMap<(i8, u8), String>
+}
+
+pub enum Outcome {
+    Pass,
+    Fail { expected : String, actual : String },
+    // Solved but there is no expected answer to compare against, this is
+    // reported without failing since a new day has nothing recorded yet
+    Missing { actual : String },
+    // An answer is expected but no part produced one
+    Unanswered { expected : String },
+    // The input could not be loaded or the part could not be run
+    Error(String)
+}
+
+pub struct Check {
+    pub day : i8,
+    pub part : u8,
+    pub outcome : Outcome
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub passed : usize,
+    pub failed : usize,
+    pub missing : usize
+}
+
+type Answer = ((i8, u8), String);
+
+fn parse_answer(line : &str) -> Result<Option<Answer>, ParseError> {
+    let content = line.split('#').next().unwrap_or("").trim();
+    if content.is_empty() {
+        return Ok(None);
+    }
+    let mut parts = content.splitn(3, char::is_whitespace);
+    let day_str = parts.next().unwrap_or("");
+    let day = day_str.parse::<i8>().map_err(
+        |_| ParseError::at(line, day_str, "expected a day")
+    )?;
+    let part_str = parts.next().unwrap_or("");
+    let part = part_str.parse::<u8>().map_err(
+        |_| ParseError::at(line, part_str, "expected a part")
+    )?;
+    let answer = parts.next().map(str::trim).filter(
+        |answer| !answer.is_empty()
+    ).ok_or(
+        ParseError::new(content.len() + 1, "expected an answer")
+    )?;
+    Ok(Some(((day, part), answer.to_string())))
+}
+
+impl Expected {
+    pub fn from_lines<I>(lines : I) -> Result<Expected, ParseError>
+      where I : Iterator<Item = String>
+    {
+        let mut answers = BTreeMap::new();
+        for (index, line) in lines.enumerate() {
+            let parsed = parse_answer(&line).map_err(
+                |err| err.offset_lines(index)
+            )?;
+            if let Some((key, answer)) = parsed {
+                answers.insert(key, answer);
+            }
+        }
+        Ok(Expected { answers : answers })
+    }
+
+    pub fn load(path : &Path) -> Result<Expected, Error> {
+        let data = fs::read_to_string(path)?;
+        Ok(Expected::from_lines(
+            data.lines().map(|line| line.to_string())
+        )?)
+    }
+
+    pub fn get(self : &Self, day : i8, part : u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(
+            |answer| answer.as_str()
+        )
+    }
+
+    fn parts(self : &Self, day : i8) -> Vec<u8> {
+        self.answers.keys().filter(
+            |(answer_day, _)| *answer_day == day
+        ).map(
+            |(_, part)| *part
+        ).collect()
+    }
+}
+
+impl Check {
+    pub fn passed(self : &Self) -> bool {
+        matches!(self.outcome, Outcome::Pass)
+    }
+}
+
+fn check_entry(entry : &Entry, expected : &Expected, source : &Source) -> Vec<Check> {
+    let solution = match entry.parse(source) {
+        Ok(solution) => solution,
+        Err(err) => {
+            // Every expected part of the day fails, part 1 stands in when none is
+            let mut parts = expected.parts(entry.day);
+            if parts.is_empty() {
+                parts.push(1);
+            }
+            return parts.into_iter().map(
+                |part| Check { day : entry.day, part : part, outcome : Outcome::Error(err.to_string()) }
+            ).collect();
+        }
+    };
+    (1..=2).filter_map(
+        |part| {
+            let answer = match solution.solve(part) {
+                Ok(Some(answer)) => answer,
+                Ok(None) => return expected.get(entry.day, part).map(
+                    |value| Check { day : entry.day, part : part, outcome : Outcome::Unanswered { expected : value.to_string() } }
+                ),
+                Err(err) => return Some(Check { day : entry.day, part : part, outcome : Outcome::Error(err.to_string()) })
+            };
+            let actual = answer.value().to_string();
+            let outcome = match expected.get(entry.day, part) {
+                Some(value) if value == actual => Outcome::Pass,
+                Some(value) => Outcome::Fail { expected : value.to_string(), actual : actual },
+                None => Outcome::Missing { actual : actual }
+            };
+            Some(Check { day : entry.day, part : part, outcome : outcome })
+        }
+    ).collect()
+}
+
+// Run every registered solution against its input and compare with expected
+pub fn verify(registry : &[Entry], expected : &Expected, source : &Source) -> Vec<Check> {
+    let mut checks : Vec<Check> = registry.iter().flat_map(
+        |entry| check_entry(entry, expected, source)
+    ).collect();
+    // Expected answers for days that have no registered solution
+    for ((day, part), value) in expected.answers.iter() {
+        if !registry.iter().any(|entry| entry.day == *day) {
+            checks.push(Check { day : *day, part : *part, outcome : Outcome::Unanswered { expected : value.clone() } });
+        }
+    }
+    checks
+}
+
+pub fn summarize(checks : &[Check]) -> Summary {
+    let mut summary = Summary::default();
+    for check in checks {
+        match check.outcome {
+            Outcome::Pass => summary.passed += 1,
+            Outcome::Missing { .. } => summary.missing += 1,
+            Outcome::Fail { .. } | Outcome::Unanswered { .. } | Outcome::Error(_) => summary.failed += 1
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize;
+    use super::verify;
+    use super::Expected;
+    use super::Outcome;
+    use crate::challenge::Day1;
+    use crate::io::Source;
+    use crate::solution::Entry;
+
+    fn expected(text : &str) -> Expected {
+        Expected::from_lines(text.lines().map(|line| line.to_string())).unwrap()
+    }
+
+    #[test]
+    fn answers_file() {
+        let expected = expected("# comment\n\n1 1 514579\n21 2 a,b,c  # trailing\n");
+        assert_eq!(expected.get(1, 1), Some("514579"));
+        assert_eq!(expected.get(21, 2), Some("a,b,c"));
+        assert_eq!(expected.get(1, 2), None);
+        let err = Expected::from_lines(vec!("1 1 2".to_string(), "1 x 2".to_string()).into_iter()).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+
+    #[test]
+    fn outcomes() {
+        let registry = vec!(Entry::new::<Day1>(1));
+        let source = Source::Text("1721\n979\n366\n299\n675\n1456");
+        let checks = verify(&registry, &expected("1 1 514579"), &source);
+        assert!(checks[0].passed());
+        assert!(matches!(&checks[1].outcome, Outcome::Missing { actual } if actual == "241861950"));
+        let checks = verify(&registry, &expected("1 1 1\n1 2 241861950"), &source);
+        assert!(matches!(&checks[0].outcome, Outcome::Fail { .. }));
+        let summary = summarize(&checks);
+        assert_eq!((summary.passed, summary.failed, summary.missing), (1, 1, 0));
+        let checks = verify(&registry, &expected(""), &Source::Text("x"));
+        assert!(matches!(checks[0].outcome, Outcome::Error(_)));
+    }
+
+    #[test]
+    fn failures() {
+        let registry = vec!(Entry::new::<Day1>(1));
+        let checks = verify(&registry, &expected("1 1 514579\n1 2 241861950"), &Source::Text("x"));
+        assert_eq!(checks.iter().map(|check| check.part).collect::<Vec<u8>>(), vec!(1, 2));
+        assert!(checks.iter().all(|check| matches!(check.outcome, Outcome::Error(_))));
+        let source = Source::Text("1721\n979\n366\n299\n675\n1456");
+        let checks = verify(&registry, &expected("1 1 514579\n2 1 2"), &source);
+        assert!(matches!(&checks[2].outcome, Outcome::Unanswered { expected } if expected == "2"));
+        assert_eq!((checks[2].day, checks[2].part), (2, 1));
+        let summary = summarize(&checks);
+        assert_eq!((summary.passed, summary.failed, summary.missing), (1, 1, 1));
+    }
+}
@@ -0,0 +1,62 @@
+// Run each day's solution on its input in data/ and compare against data/answers.txt
+
+use std::path::Path;
+
+use advent_of_code::challenge;
+use advent_of_code::io::Source;
+use advent_of_code::solution;
+use advent_of_code::verify;
+
+fn check(day : i8) {
+    let expected = verify::Expected::load(Path::new("data/answers.txt")).unwrap();
+    let registry = challenge::registry();
+    let entry = solution::find(&registry, day).unwrap();
+    let solution = entry.parse(&Source::data()).unwrap();
+    for part in 1..=2 {
+        match (solution.solve(part).unwrap(), expected.get(day, part)) {
+            (Some(answer), Some(value)) => assert_eq!(answer.value(), value, "day {} part {}", day, part),
+            (None, None) => (),
+            (answer, value) => panic!("day {} part {}: solved {:?}, expected {:?}", day, part, answer, value)
+        }
+    }
+}
+
+#[test] fn day1() { check(1) }
+#[test] fn day2() { check(2) }
+#[test] fn day3() { check(3) }
+#[test] fn day4() { check(4) }
+#[test] fn day5() { check(5) }
+#[test] fn day6() { check(6) }
+#[test] fn day7() { check(7) }
+#[test] fn day8() { check(8) }
+#[test] fn day9() { check(9) }
+#[test] fn day10() { check(10) }
+#[test] fn day11() { check(11) }
+#[test] fn day12() { check(12) }
+#[test] fn day13() { check(13) }
+#[test] fn day14() { check(14) }
+// Thirty million turns, run with cargo test -- --ignored
+#[test] #[ignore] fn day15() { check(15) }
+#[test] fn day16() { check(16) }
+#[test] fn day17() { check(17) }
+#[test] fn day18() { check(18) }
+#[test] fn day19() { check(19) }
+#[test] fn day20() { check(20) }
+#[test] fn day21() { check(21) }
+#[test] fn day22() { check(22) }
+#[test] fn day23() { check(23) }
+#[test] fn day24() { check(24) }
+#[test] fn day25() { check(25) }
+
+// Totals past an i64 stay exact, and a line that can not be evaluated is an error
+#[test]
+fn day18_totals() {
+    let registry = challenge::registry();
+    let entry = solution::find(&registry, 18).unwrap();
+    let solution = entry.parse(&Source::Text("9223372036854775807 + 1\n9223372036854775807 * 2")).unwrap();
+    assert_eq!(solution.solve(1).unwrap().unwrap().value(), "27670116110564327422");
+    assert_eq!(solution.solve(2).unwrap().unwrap().value(), "27670116110564327422");
+    let solution = entry.parse(&Source::Text("1 + 2\n2 / (1 - 1)")).unwrap();
+    let err = solution.solve(1).err().unwrap();
+    assert_eq!(err.to_string(), "could not evaluate (2 / (1 - 1)): division by zero");
+}